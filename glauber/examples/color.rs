@@ -6,13 +6,11 @@ use std::convert::TryInto;
 
 use std::path::PathBuf;
 use std::time::Instant;
-use std::u32;
 
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde_json::json;
 use structopt::StructOpt;
 
-use glauber::graph::Graph;
 use glauber::{color, graphio, Scanner, SummaryStats};
 
 /// Reads simplified graph format files.
@@ -43,9 +41,9 @@ struct Opt {
     #[structopt(long)]
     graph: Vec<PathBuf>,
 
-    /// Total number of Glauber samples.
+    /// Total number of Glauber samples, by default `color::auto_nsamples`.
     #[structopt(long)]
-    nsamples: usize,
+    nsamples: Option<usize>,
 
     /// Number of steps to take between coloring observations
     #[structopt(long)]
@@ -61,7 +59,7 @@ struct Opt {
 
     /// Random seed
     #[structopt(long)]
-    seed: usize,
+    seed: u64,
 }
 
 fn main() {
@@ -78,11 +76,7 @@ fn main() {
         })
     );
 
-    let max_degree = (0..graph.nvertices())
-        .into_iter()
-        .map(|v| graph.degree(v.try_into().unwrap()))
-        .max()
-        .expect("nonempty");
+    let max_degree = graph.max_degree();
 
     println!(
         "{}",
@@ -90,16 +84,21 @@ fn main() {
             "nvertices": graph.nvertices(),
             "nedges": graph.nedges(),
             "max_degree": max_degree,
+            "min_degree": graph.min_degree(),
+            "avg_degree": graph.avg_degree(),
         })
     );
 
     let ncolors = 2 * max_degree + 1;
     let ncolors: u32 = ncolors.try_into().unwrap();
+    let nsamples = opt
+        .nsamples
+        .unwrap_or_else(|| color::auto_nsamples(&graph, ncolors));
     let colors_start = Instant::now();
-    let colors = color::glauber(
+    let (colors, actual_ncolors) = color::glauber(
         &graph,
         ncolors,
-        opt.nsamples,
+        nsamples,
         opt.frequency,
         &opt.out,
         &opt.out_times,
@@ -110,17 +109,21 @@ fn main() {
         "{}",
         json!({
             "ncolors": ncolors,
+            "actual_ncolors": actual_ncolors,
+            "nsamples": nsamples,
             "color_cardinalities": compute_color_cardinalities(&colors, &remap),
             "colors_duration": format!("{:.0?}", Instant::now().duration_since(colors_start)),
         })
     );
 
-    assert!(check_proper_coloring(&graph, &colors));
+    if let Err(conflicts) = color::verify_proper_coloring(&graph, &colors) {
+        panic!("improper coloring, conflicting edges {:?}", conflicts);
+    }
 }
 
 /// Returns a set of summary statistics over the cardinality (number of features)
-/// mapping to each color column.
-fn compute_color_cardinalities(colors: &[u32], remap: &[u32]) -> HashMap<String, f64> {
+/// mapping to each color column, along with a histogram of them.
+fn compute_color_cardinalities(colors: &[u32], remap: &[u32]) -> serde_json::Value {
     let cards = remap.iter().copied().enumerate().fold(
         HashMap::new(),
         |mut acc: HashMap<u32, u32>, (feature, remap_val)| {
@@ -129,17 +132,9 @@ fn compute_color_cardinalities(colors: &[u32], remap: &[u32]) -> HashMap<String,
             acc
         },
     );
-    SummaryStats::from(cards.values().map(|x| *x as f64)).to_map()
-}
-
-fn check_proper_coloring(graph: &Graph, colors: &[u32]) -> bool {
-    (0..graph.nvertices()).into_par_iter().all(|v| {
-        for &nbr in graph.neighbors(v as u32) {
-            let nbr = nbr as usize;
-            if colors[v] == colors[nbr] {
-                return false;
-            }
-        }
-        true
-    })
+    let stats = SummaryStats::from_parallel(cards.par_iter().map(|(_, x)| *x as f64));
+    let mut json = stats.to_json();
+    let hist = stats.histogram(cards.values().map(|&x| x as f64), 10);
+    json["histogram"] = json!(hist.to_vec());
+    json
 }
@@ -7,10 +7,8 @@ use std::io::BufWriter;
 use std::io::{Write};
 use std::path::PathBuf;
 use std::time::Instant;
-use std::u32;
 
 use rand::Rng;
-use rand_pcg::Lcg64Xsh32;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 use serde_json::json;
@@ -55,7 +53,7 @@ fn main() {
         })
         .collect();
 
-    let mut rng = Lcg64Xsh32::new(0xcafef00dd15ea5e5, opt.seed);
+    let mut rng = glauber::seeded_rng(opt.seed);
     let to_sample = n * opt.degree;
     let sample_start = Instant::now();
     let additional =
@@ -73,7 +71,7 @@ fn main() {
 
     let indexing_start = Instant::now();
     let mut neighbors: HashMap<_, Vec<_>> = (0..n).map(|v| (v as u32, Vec::new())).collect();
-    for (from, to) in edges.into_iter().chain(additional.into_iter()).map(totup) {
+    for (from, to) in edges.into_iter().chain(additional).map(totup) {
         neighbors.get_mut(&from).expect("vertex").push(to);
     }
     println!(
@@ -84,7 +82,7 @@ fn main() {
     );
 
     let lines_per_file = 10000;
-    let nfiles = (n + lines_per_file - 1) / lines_per_file;
+    let nfiles = n.div_ceil(lines_per_file);
 
     let write_graph_start = Instant::now();
     (0..nfiles).into_par_iter().for_each(|file_ix| {
@@ -105,7 +103,7 @@ fn main() {
             for nbr in &neighbors[&v] {
                 write!(writer, " {}", nbr).expect("write dest");
             }
-            write!(writer, "\n").expect("newline");
+            writeln!(writer).expect("newline");
         }
     });
 
@@ -203,7 +201,7 @@ fn fromtup(i: u32, j: u32) -> Edge {
 
 fn totup(x: Edge) -> (u32, u32) {
     let larger = (8 * x + 1) as f64;
-    let diagonal = ((larger.sqrt() as u64) + 1) / 2 - 1;
+    let diagonal = (larger.sqrt() as u64).div_ceil(2) - 1;
     let i = x - c2(diagonal + 1);
     let j = diagonal - i;
     let j = j + i + 1;
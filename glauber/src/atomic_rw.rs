@@ -1,7 +1,10 @@
 //! An atomic-based read-write lockable U32.
 
+use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
 /// A read-write lockable U32, backed by an atomic U64.
 ///
 /// Only supports try-locking.
@@ -80,11 +83,67 @@ impl Rwu32 {
         }
     }
 
+    /// Extracts the value without checking the lock state.
+    ///
+    /// # Safety
+    ///
+    /// No guard may still be live, or have been leaked, for this `Rwu32`,
+    /// otherwise the extracted value may be one that a writer meant to
+    /// replace.
+    pub unsafe fn into_inner_unchecked(self) -> u32 {
+        from_payload(self.inner.into_inner())
+    }
+
     pub fn mut_read(&mut self) -> u32 {
         from_payload(*self.inner.get_mut())
     }
 }
 
+/// An [`Rwu32`] padded out to its own cache line, so that threads locking
+/// neighboring entries of a slice of these don't contend over the same line.
+#[repr(align(64))]
+pub struct AlignedRwu32(Rwu32);
+
+impl AlignedRwu32 {
+    pub fn new(init: u32) -> Self {
+        Self(Rwu32::new(init))
+    }
+
+    /// Extracts every value in parallel, skipping the lock checks of
+    /// [`Rwu32::into_inner`] outside of debug builds. Owning the `Vec` means
+    /// no guards borrow from it, so this is safe as long as no guards were
+    /// leaked.
+    pub fn drain_unchecked(colors: Vec<AlignedRwu32>) -> Vec<u32> {
+        colors
+            .into_par_iter()
+            .map(|x| {
+                if cfg!(debug_assertions) {
+                    x.0.into_inner()
+                } else {
+                    // SAFETY: we own every entry, so no guard can still
+                    // borrow one, and a leaked guard could at worst have
+                    // left a stale payload, which is still a valid u32
+                    unsafe { x.0.into_inner_unchecked() }
+                }
+            })
+            .collect()
+    }
+}
+
+impl Deref for AlignedRwu32 {
+    type Target = Rwu32;
+
+    fn deref(&self) -> &Rwu32 {
+        &self.0
+    }
+}
+
+impl DerefMut for AlignedRwu32 {
+    fn deref_mut(&mut self) -> &mut Rwu32 {
+        &mut self.0
+    }
+}
+
 fn to_payload(v: u32) -> u64 {
     u64::from(v) << 32
 }
@@ -163,3 +222,36 @@ impl<'a> Drop for ReadGuard<'a> {
         debug_assert!(result & ((1 << 31) - 1) > 0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Compares draining 10M entries with per-entry checks, sequentially,
+    /// against [`AlignedRwu32::drain_unchecked`]. Run with
+    /// `cargo test --release -- --ignored --nocapture drain`.
+    #[test]
+    #[ignore]
+    fn bench_drain() {
+        let n = 10_000_000;
+        let make = || (0..n).map(AlignedRwu32::new).collect::<Vec<_>>();
+
+        let colors = make();
+        let start = Instant::now();
+        let checked: Vec<u32> = colors.into_iter().map(|x| x.0.into_inner()).collect();
+        let checked_time = Instant::now().duration_since(start);
+
+        let colors = make();
+        let start = Instant::now();
+        let drained = AlignedRwu32::drain_unchecked(colors);
+        let drained_time = Instant::now().duration_since(start);
+
+        assert_eq!(checked, drained);
+        println!(
+            "checked {:.0?} drain_unchecked {:.0?}",
+            checked_time, drained_time
+        );
+    }
+}
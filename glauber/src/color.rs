@@ -1,22 +1,36 @@
 //! The core coloring functionality, including Glauber dynamics simulation.
 
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Read, Write};
+use std::iter;
 use std::path::Path;
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::time::Instant;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use rand::Rng;
-use rand_pcg::Lcg64Xsh32;
+use good_lp::{microlp, variable, Expression, ProblemVariables, Solution, SolverModel, Variable};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
+use rayon::slice::ParallelSlice;
+use rayon::slice::ParallelSliceMut;
 use serde_json::json;
 
 use crate::{
-    atomic_rw::{ReadGuard, Rwu32},
+    atomic_rw::{AlignedRwu32, ReadGuard},
     graph::Graph,
     graph::Vertex,
+    graph::{
+        clique_lower_bound, connected_components, degeneracy_peeling, is_bipartite,
+        kcore_decomposition,
+    },
+    seeded_rng, thread_seed,
 };
 
 /// Given the training set, a color mapping, and the number of colors,
@@ -30,102 +44,1677 @@ pub fn remap(ncolors: u32, colors: &[u32]) -> Vec<u32> {
     let mut remap = vec![0u32; colors.len()];
     colors.iter().copied().enumerate().for_each(|(f, c)| {
         color_counts[c as usize] += 1;
-        remap[f as usize] = color_counts[c as usize]
+        remap[f] = color_counts[c as usize]
     });
 
     remap
 }
 
-/// Returns `(ncolors, colors)` for a max-degree-ordered coloring of the graph.
-pub fn greedy(graph: &Graph) -> (u32, Vec<u32>) {
+/// Inverts [`remap`], returning `inverse` such that `inverse[c]` lists the
+/// features with color `c` in order of their rank, i.e.,
+/// `inverse[colors[f]][remap[f] - 1] == f`.
+pub fn remap_inverse(ncolors: u32, colors: &[u32], remap: &[u32]) -> Vec<Vec<u32>> {
+    let mut color_counts = vec![0usize; ncolors as usize];
+    colors.iter().for_each(|&c| color_counts[c as usize] += 1);
+    let mut inverse: Vec<Vec<u32>> = color_counts
+        .into_iter()
+        .map(|count| vec![0; count])
+        .collect();
+    colors
+        .iter()
+        .zip(remap)
+        .enumerate()
+        .for_each(|(f, (&c, &r))| inverse[c as usize][r as usize - 1] = f as u32);
+    inverse
+}
+
+/// Checks that no edge is monochromatic, returning all the conflicting edges
+/// `(u, v)`, with `u < v` and in sorted order, otherwise.
+pub fn verify_proper_coloring(graph: &Graph, colors: &[u32]) -> Result<(), Vec<(Vertex, Vertex)>> {
+    assert!(colors.len() == graph.nvertices());
+    let mut conflicts: Vec<_> = graph
+        .edges_par()
+        .filter(|&(u, v)| colors[u as usize] == colors[v as usize])
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    conflicts.par_sort_unstable();
+    Err(conflicts)
+}
+
+/// Returns the symmetric `ncolors` by `ncolors` matrix where entry
+/// `[c1][c2]` counts the edges with one endpoint colored `c1` and the other
+/// `c2`. Every edge counts once, but shows up in both `[c1][c2]` and
+/// `[c2][c1]`; the diagonal counts the conflicting edges, so it's all zeros
+/// for a proper coloring.
+pub fn conflict_matrix(graph: &Graph, colors: &[u32], ncolors: u32) -> Vec<Vec<u32>> {
+    assert!(colors.len() == graph.nvertices());
+    let n = ncolors as usize;
+    let counts = graph
+        .edges_par()
+        .fold(
+            || vec![0u32; n * n],
+            |mut counts, (u, v)| {
+                let (cu, cv) = (colors[u as usize] as usize, colors[v as usize] as usize);
+                counts[cu * n + cv] += 1;
+                if cu != cv {
+                    counts[cv * n + cu] += 1;
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![0u32; n * n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        );
+    counts.chunks(n.max(1)).map(<[u32]>::to_vec).collect()
+}
+
+/// Returns the subgraph induced by the vertices colored `c1` or `c2`, in
+/// their relative order, which is bipartite when `colors` is proper.
+pub fn color_class_subgraph(graph: &Graph, colors: &[u32], c1: u32, c2: u32) -> Graph {
+    let mask: Vec<bool> = colors.iter().map(|&c| c == c1 || c == c2).collect();
+    graph.subgraph_by_vertices(&mask)
+}
+
+/// Evens out the color class sizes of a proper coloring in place, returning the
+/// final class sizes.
+///
+/// Repeatedly moves some vertex of the largest class to the smallest class it
+/// can join without conflicting with its neighbors, as long as that shrinks
+/// the gap between the two. Each move strictly decreases the sum of squared
+/// class sizes, so this stops once no vertex of the largest class can move,
+/// or after `nvertices` moves.
+pub fn balance_classes(graph: &Graph, colors: &mut [u32], ncolors: u32) -> Vec<usize> {
+    let ncolors = ncolors as usize;
+    let mut members: Vec<Vec<Vertex>> = vec![Vec::new(); ncolors];
+    let mut position = vec![0usize; colors.len()];
+    for (v, &c) in colors.iter().enumerate() {
+        position[v] = members[c as usize].len();
+        members[c as usize].push(v as Vertex);
+    }
+
+    let mut neighbor_colors = vec![false; ncolors];
+    let mut by_size: Vec<usize> = (0..ncolors).collect();
+    for _ in 0..colors.len() {
+        by_size.sort_unstable_by_key(|&c| members[c].len());
+        let largest = match by_size.last() {
+            Some(&c) => c,
+            None => break,
+        };
+        let largest_size = members[largest].len();
+
+        let mut found = None;
+        for &v in &members[largest] {
+            for &w in graph.neighbors(v) {
+                neighbor_colors[colors[w as usize] as usize] = true;
+            }
+            found = by_size
+                .iter()
+                .copied()
+                .take_while(|&c| members[c].len() + 1 < largest_size)
+                .find(|&c| !neighbor_colors[c])
+                .map(|c| (v, c));
+            for &w in graph.neighbors(v) {
+                neighbor_colors[colors[w as usize] as usize] = false;
+            }
+            if found.is_some() {
+                break;
+            }
+        }
+
+        let (v, target) = match found {
+            Some(found) => found,
+            None => break,
+        };
+        let ix = position[v as usize];
+        members[largest].swap_remove(ix);
+        if let Some(&moved) = members[largest].get(ix) {
+            position[moved as usize] = ix;
+        }
+        position[v as usize] = members[target].len();
+        members[target].push(v);
+        colors[v as usize] = target as u32;
+    }
+
+    members.iter().map(Vec::len).collect()
+}
+
+/// Tries to empty out the smallest class of a proper coloring with `ncolors`
+/// colors, using Kempe chain swaps, returning whether it succeeded.
+///
+/// Each vertex `v` of the smallest class moves to some color `a` once its
+/// `a`-colored neighbors are out of the way: this swaps the `{a, b}` Kempe
+/// chains through them, for some third color `b`, so long as the chains
+/// don't reach a `b`-colored neighbor of `v`. On success the last color takes
+/// over the freed label, so `colors` only uses `ncolors - 1` colors. On
+/// failure `colors` stays proper, though vertices may have been recolored.
+pub fn try_kempe_reduction(graph: &Graph, colors: &mut [u32], ncolors: u32) -> bool {
+    if ncolors <= 1 {
+        return false;
+    }
+    let mut sizes = vec![0usize; ncolors as usize];
+    for &c in colors.iter() {
+        sizes[c as usize] += 1;
+    }
+    let smallest = (0..ncolors).min_by_key(|&c| sizes[c as usize]).unwrap();
+    let class: Vec<Vertex> = (0..colors.len() as Vertex)
+        .filter(|&v| colors[v as usize] == smallest)
+        .collect();
+
+    let mut in_chain = vec![false; colors.len()];
+    for v in class {
+        let moved = (0..ncolors).filter(|&a| a != smallest).any(|a| {
+            let seeds: Vec<Vertex> = graph
+                .neighbors(v)
+                .iter()
+                .copied()
+                .filter(|&w| colors[w as usize] == a)
+                .collect();
+            if seeds.is_empty() {
+                colors[v as usize] = a;
+                return true;
+            }
+            for b in (0..ncolors).filter(|&b| b != smallest && b != a) {
+                let chain = kempe_chain(graph, colors, &seeds, a, b, &mut in_chain);
+                let blocked = graph
+                    .neighbors(v)
+                    .iter()
+                    .any(|&w| colors[w as usize] == b && in_chain[w as usize]);
+                for &w in &chain {
+                    in_chain[w as usize] = false;
+                }
+                if !blocked {
+                    for &w in &chain {
+                        colors[w as usize] = if colors[w as usize] == a { b } else { a };
+                    }
+                    colors[v as usize] = a;
+                    return true;
+                }
+            }
+            false
+        });
+        if !moved {
+            return false;
+        }
+    }
+
+    let last = ncolors - 1;
+    for c in colors.iter_mut() {
+        if *c == last {
+            *c = smallest;
+        }
+    }
+    true
+}
+
+/// Collects the vertices reachable from `seeds` through vertices colored `a`
+/// or `b`, marking them in `in_chain`.
+fn kempe_chain(
+    graph: &Graph,
+    colors: &[u32],
+    seeds: &[Vertex],
+    a: u32,
+    b: u32,
+    in_chain: &mut [bool],
+) -> Vec<Vertex> {
+    let mut chain = Vec::new();
+    for &s in seeds {
+        if !in_chain[s as usize] {
+            in_chain[s as usize] = true;
+            chain.push(s);
+        }
+    }
+    let mut next = 0;
+    while next < chain.len() {
+        let u = chain[next];
+        next += 1;
+        for &w in graph.neighbors(u) {
+            let c = colors[w as usize];
+            if (c == a || c == b) && !in_chain[w as usize] {
+                in_chain[w as usize] = true;
+                chain.push(w);
+            }
+        }
+    }
+    chain
+}
+
+/// Shrinks the palette of a proper coloring with `ncolors` colors in place,
+/// returning the number of colors left, which are relabeled in their original
+/// order to `0` through one less than that.
+///
+/// Classes are visited from smallest to largest, and each one is dissolved if
+/// every one of its vertices can move to some other remaining color which
+/// none of its neighbors use, preferring larger classes; otherwise the class
+/// is left alone. Since a class has no internal edges, its vertices can move
+/// independently of each other.
+pub fn compress_palette(graph: &Graph, colors: &mut [u32], ncolors: u32) -> u32 {
+    let ncolors = ncolors as usize;
+    let mut members: Vec<Vec<Vertex>> = vec![Vec::new(); ncolors];
+    for (v, &c) in colors.iter().enumerate() {
+        members[c as usize].push(v as Vertex);
+    }
+    let mut alive: Vec<bool> = members.iter().map(|m| !m.is_empty()).collect();
+    let mut by_size: Vec<usize> = (0..ncolors).filter(|&c| alive[c]).collect();
+    by_size.sort_unstable_by_key(|&c| members[c].len());
+
+    let mut neighbor_colors = vec![false; ncolors];
+    for &c in &by_size {
+        alive[c] = false;
+        let mut moves = Vec::with_capacity(members[c].len());
+        for &v in &members[c] {
+            for &w in graph.neighbors(v) {
+                neighbor_colors[colors[w as usize] as usize] = true;
+            }
+            let target = by_size
+                .iter()
+                .rev()
+                .copied()
+                .find(|&d| alive[d] && !neighbor_colors[d]);
+            for &w in graph.neighbors(v) {
+                neighbor_colors[colors[w as usize] as usize] = false;
+            }
+            match target {
+                Some(d) => moves.push((v, d)),
+                None => break,
+            }
+        }
+        if moves.len() < members[c].len() {
+            alive[c] = true;
+            continue;
+        }
+        members[c].clear();
+        for (v, d) in moves {
+            colors[v as usize] = d as u32;
+            members[d].push(v);
+        }
+    }
+
+    let mut relabel = vec![0u32; ncolors];
+    let mut nalive = 0;
+    for (label, _) in relabel.iter_mut().zip(&alive).filter(|(_, &a)| a) {
+        *label = nalive;
+        nalive += 1;
+    }
+    colors.iter_mut().for_each(|c| *c = relabel[*c as usize]);
+    nalive
+}
+
+/// The order in which [`greedy`] colors vertices.
+pub enum VertexOrdering {
+    /// Non-increasing degree, the usual Welsh-Powell order.
+    DegreeLargestFirst,
+    /// Non-decreasing degree.
+    DegreeSmallestFirst,
+    /// A uniformly random permutation, shuffled with the given seed.
+    Random(u64),
+    /// Increasing vertex id.
+    Natural,
+    /// The reverse of the order in which vertices are peeled off by
+    /// repeatedly removing one of minimum remaining degree, which colors a
+    /// graph of degeneracy `k` with at most `k + 1` colors.
+    SmallestLast,
+    /// This permutation of the vertices. [`greedy`] panics if it isn't one.
+    Custom(Vec<Vertex>),
+}
+
+impl VertexOrdering {
+    fn vertices(self, graph: &Graph) -> Vec<Vertex> {
+        match self {
+            Self::DegreeLargestFirst => graph.vertices_by_degree(true),
+            Self::DegreeSmallestFirst => graph.vertices_by_degree(false),
+            Self::Random(seed) => {
+                let mut rng = seeded_rng(seed);
+                let mut vertices: Vec<Vertex> = (0..graph.nvertices() as Vertex).collect();
+                vertices.shuffle(&mut rng);
+                vertices
+            }
+            Self::Natural => (0..graph.nvertices() as Vertex).collect(),
+            Self::SmallestLast => {
+                let (mut vertices, _) = degeneracy_peeling(graph);
+                vertices.reverse();
+                vertices
+            }
+            Self::Custom(vertices) => {
+                assert!(
+                    vertices.len() == graph.nvertices(),
+                    "custom ordering of {} vertices for {} vertex graph",
+                    vertices.len(),
+                    graph.nvertices()
+                );
+                let mut seen = vec![false; vertices.len()];
+                for &v in &vertices {
+                    assert!(
+                        seen.get(v as usize) == Some(&false),
+                        "custom ordering repeats or is missing a vertex, at {}",
+                        v
+                    );
+                    seen[v as usize] = true;
+                }
+                vertices
+            }
+        }
+    }
+}
+
+/// Returns `(ncolors, colors)` for the greedy coloring of the graph which
+/// gives each vertex, in the given order, the smallest color not yet used by
+/// its neighbors.
+pub fn greedy(graph: &Graph, ordering: VertexOrdering) -> (u32, Vec<u32>) {
     let nvertices = graph.nvertices();
-    let mut vertices: Vec<_> = (0..nvertices).map(|v| v as Vertex).collect();
 
-    let sort_start = Instant::now();
-    vertices.sort_unstable_by_key(|&v| graph.degree(v));
-    let sort_time = format!("{:.0?}", Instant::now().duration_since(sort_start));
+    let sort_start = Instant::now();
+    let vertices = ordering.vertices(graph);
+    let sort_time = format!("{:.0?}", Instant::now().duration_since(sort_start));
+
+    const NO_COLOR: u32 = u32::MAX;
+    let mut colors: Vec<u32> = vec![NO_COLOR; nvertices];
+    let mut adjacent_colors: Vec<bool> = Vec::new();
+
+    let greedy_start = Instant::now();
+    for vertex in vertices {
+        // loop invariant is that none of adjacent_colors elements are true
+
+        // what color are our neighbors?
+        let mut nadjacent_colors = 0;
+        for &n in graph.neighbors(vertex) {
+            let n = n as usize;
+            if colors[n] == NO_COLOR {
+                continue;
+            }
+
+            let c = colors[n] as usize;
+            if !adjacent_colors[c] {
+                adjacent_colors[c] = true;
+                nadjacent_colors += 1;
+                if nadjacent_colors == colors.len() {
+                    break;
+                }
+            }
+        }
+
+        // what's the smallest color not in our neighbors?
+        let chosen = if nadjacent_colors == adjacent_colors.len() {
+            adjacent_colors.push(false);
+            adjacent_colors.len() - 1
+        } else {
+            adjacent_colors.iter().copied().position(|x| !x).unwrap()
+        };
+        colors[vertex as usize] = chosen as u32;
+
+        // retain loop invariant, unset neighbor colors
+        if graph.degree(vertex) >= adjacent_colors.len() {
+            graph
+                .neighbors(vertex)
+                .iter()
+                .map(|&n| colors[n as usize])
+                .filter(|&n| n != NO_COLOR)
+                .for_each(|c| {
+                    adjacent_colors[c as usize] = false;
+                });
+        } else {
+            for c in adjacent_colors.iter_mut() {
+                *c = false;
+            }
+        }
+    }
+    let greedy_time = format!("{:.0?}", Instant::now().duration_since(greedy_start));
+
+    let ncolors = adjacent_colors.len();
+
+    println!(
+        "{}",
+        json!({
+            "vertex_sort_time": sort_time,
+            "greedy_color_time": greedy_time,
+            "greedy_ncolors": ncolors,
+        })
+    );
+
+    (ncolors as u32, colors)
+}
+
+/// [`greedy`] with the [`VertexOrdering::SmallestLast`] order, also logging
+/// the degeneracy bound on the number of colors it can use.
+pub fn greedy_smallest_last(graph: &Graph) -> (u32, Vec<u32>) {
+    let (ncolors, colors) = greedy(graph, VertexOrdering::SmallestLast);
+    let degeneracy = kcore_decomposition(graph).into_iter().max().unwrap_or(0);
+    println!(
+        "{}",
+        json!({
+            "smallest_last_ncolors": ncolors,
+            "degeneracy_color_bound": degeneracy + 1,
+        })
+    );
+    (ncolors, colors)
+}
+
+/// Most independent sets [`fractional_chromatic_lb`] adds to its LP before
+/// giving up on convergence.
+const FRACTIONAL_CHROMATIC_MAX_COLUMNS: usize = 1000;
+
+/// Most independent sets [`fractional_chromatic_lb`] adds per LP solve.
+const FRACTIONAL_CHROMATIC_COLUMNS_PER_SOLVE: usize = 16;
+
+/// Most candidate vertices the exact independent set search in
+/// [`fractional_chromatic_lb`] visits per call before giving up.
+const FRACTIONAL_CHROMATIC_SEARCH_WORK: usize = 1 << 22;
+
+/// Time [`fractional_chromatic_lb`] spends looking for a clique when its LP
+/// value isn't certified.
+const FRACTIONAL_CHROMATIC_CLIQUE_TIME: Duration = Duration::from_secs(1);
+
+/// Returns a lower bound on the fractional chromatic number of the graph,
+/// the LP relaxation of coloring, which lies between the clique number and
+/// the chromatic number.
+///
+/// The fractional chromatic number is the least total weight on independent
+/// sets which covers every vertex with weight at least one. Its LP dual puts
+/// weights on vertices instead, maximizing their total subject to every
+/// independent set weighing at most one. This solves the dual by column
+/// generation over a growing family of independent sets, starting from the
+/// classes of a greedy coloring: after each LP solve, a greedy oracle looks
+/// for independent sets weighing more than one under the LP's vertex
+/// weights and adds a few of the heaviest. Once it finds none, an exact
+/// branch and bound search either adds the heaviest set or proves that no
+/// set weighs more than one, in which case the LP value is the fractional
+/// chromatic number.
+///
+/// Otherwise, dividing the LP's vertex weights by the weight `w` of the
+/// heaviest independent set makes them feasible for the dual, so the LP value
+/// over `w` is a lower bound, which is raised to the size of the largest
+/// clique [`clique_lower_bound`] finds if that's larger. This happens after
+/// [`FRACTIONAL_CHROMATIC_MAX_COLUMNS`] sets, which also logs a warning, and
+/// if the exact search runs out of its [`FRACTIONAL_CHROMATIC_SEARCH_WORK`]
+/// budget, in which case `w` is replaced by a greedy clique cover's upper
+/// bound on it.
+pub fn fractional_chromatic_lb(graph: &Graph) -> f64 {
+    let start = Instant::now();
+    let n = graph.nvertices();
+    if n == 0 {
+        return 0.0;
+    }
+    let (ncolors, colors) = greedy(graph, VertexOrdering::DegreeLargestFirst);
+    let mut columns = vec![Vec::new(); ncolors as usize];
+    for (v, &c) in colors.iter().enumerate() {
+        columns[c as usize].push(v as Vertex);
+    }
+
+    let (value, weights, heaviest) = loop {
+        let mut vars = ProblemVariables::new();
+        let y: Vec<Variable> = (0..n)
+            .map(|_| vars.add(variable().min(0.0).max(1.0)))
+            .collect();
+        let total: Expression = y.iter().sum();
+        let mut model = vars.maximise(&total).using(microlp);
+        for column in &columns {
+            let weight: Expression = column.iter().map(|&v| y[v as usize]).sum();
+            model.add_constraint(weight.leq(1.0));
+        }
+        let solution = model.solve().expect("fractional coloring LP");
+        let weights: Vec<f64> = y.iter().map(|&v| solution.value(v)).collect();
+        let value = solution.eval(&total);
+
+        let full = columns.len() >= FRACTIONAL_CHROMATIC_MAX_COLUMNS;
+        let sets = heavy_independent_sets(graph, &weights, FRACTIONAL_CHROMATIC_COLUMNS_PER_SOLVE);
+        if !full && !sets.is_empty() {
+            columns.extend(sets);
+            continue;
+        }
+        match heaviest_independent_set(graph, &weights, FRACTIONAL_CHROMATIC_SEARCH_WORK) {
+            HeavySetSearch::Found(_, set) if !full && !columns.contains(&set) => columns.push(set),
+            HeavySetSearch::Found(weight, _) => break (value, weights, Some(weight)),
+            HeavySetSearch::NoneHeavy => break (value, weights, Some(1.0)),
+            HeavySetSearch::GaveUp => break (value, weights, None),
+        }
+    };
+    if columns.len() >= FRACTIONAL_CHROMATIC_MAX_COLUMNS {
+        println!(
+            "{}",
+            json!({
+                "warning": "fractional coloring LP did not converge",
+                "columns": columns.len(),
+            })
+        );
+    }
+    let bound = match heaviest {
+        Some(heaviest) if heaviest <= 1.0 => value,
+        heaviest => {
+            let heaviest = heaviest.unwrap_or_else(|| clique_cover_bound(graph, &weights));
+            // weighting any clique by ones is feasible too, which may be the
+            // better bound
+            (value / heaviest.max(1.0))
+                .max(clique_lower_bound(graph, FRACTIONAL_CHROMATIC_CLIQUE_TIME) as f64)
+        }
+    };
+    println!(
+        "{}",
+        json!({
+            "fractional_chromatic_lb": bound,
+            "fractional_chromatic_lp": value,
+            "fractional_chromatic_columns": columns.len(),
+            "fractional_chromatic_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    bound
+}
+
+/// Outcome of [`heaviest_independent_set`].
+enum HeavySetSearch {
+    /// The heaviest independent set, weighing more than one, and its weight.
+    Found(f64, Vec<Vertex>),
+    /// No independent set weighs more than one.
+    NoneHeavy,
+    /// The search ran out of its work budget.
+    GaveUp,
+}
+
+/// Searches for the heaviest independent set under `weights` by branch and
+/// bound, only looking for sets weighing more than one. Vertices are branched
+/// on in decreasing weight order, and a branch is pruned once its weight plus
+/// that of its remaining candidates can't beat the best set so far.
+///
+/// The search stack is explicit, and gives up after visiting `max_work`
+/// candidate vertices, which also bounds its memory.
+fn heaviest_independent_set(graph: &Graph, weights: &[f64], max_work: usize) -> HeavySetSearch {
+    let mut candidates: Vec<Vertex> = (0..graph.nvertices() as Vertex)
+        .filter(|&v| weights[v as usize] > 0.0)
+        .collect();
+    candidates.sort_by(|&a, &b| weights[b as usize].total_cmp(&weights[a as usize]));
+    let remaining: f64 = candidates.iter().map(|&v| weights[v as usize]).sum();
+
+    let mut best: Option<(f64, Vec<Vertex>)> = None;
+    let mut floor = 1.0 + 1e-9;
+    let mut work = candidates.len();
+    // each entry is a set, its weight, its candidates and their total weight
+    let mut stack = vec![(Vec::new(), 0.0, candidates, remaining)];
+    while let Some((set, weight, candidates, remaining)) = stack.pop() {
+        if weight + remaining <= floor {
+            continue;
+        }
+        let (v, rest) = match candidates.split_first() {
+            Some((&v, rest)) => (v, rest),
+            None => {
+                floor = weight;
+                best = Some((weight, set));
+                continue;
+            }
+        };
+        work += rest.len();
+        if work > max_work {
+            return HeavySetSearch::GaveUp;
+        }
+        let excluded = rest.to_vec();
+        let included: Vec<Vertex> = rest
+            .iter()
+            .copied()
+            .filter(|&u| !graph.has_edge(v, u))
+            .collect();
+        let included_remaining: f64 = included.iter().map(|&u| weights[u as usize]).sum();
+        let mut with_v = set.clone();
+        with_v.push(v);
+        stack.push((set, weight, excluded, remaining - weights[v as usize]));
+        stack.push((
+            with_v,
+            weight + weights[v as usize],
+            included,
+            included_remaining,
+        ));
+    }
+    match best {
+        Some((weight, mut set)) => {
+            set.sort_unstable();
+            HeavySetSearch::Found(weight, set)
+        }
+        None => HeavySetSearch::NoneHeavy,
+    }
+}
+
+/// Returns an upper bound on the weight of any independent set under
+/// `weights`. The vertices are greedily covered by cliques, each grown from
+/// the heaviest uncovered vertex, and an independent set holds at most one
+/// vertex of each clique, so at most that vertex's weight.
+fn clique_cover_bound(graph: &Graph, weights: &[f64]) -> f64 {
+    let n = graph.nvertices();
+    let mut order: Vec<Vertex> = (0..n as Vertex).collect();
+    order.sort_by(|&a, &b| weights[b as usize].total_cmp(&weights[a as usize]));
+    let mut covered = vec![false; n];
+    let mut bound = 0.0;
+    let mut clique = Vec::new();
+    for v in order {
+        if covered[v as usize] {
+            continue;
+        }
+        bound += weights[v as usize];
+        clique.clear();
+        clique.push(v);
+        for &u in graph.neighbors(v) {
+            if !covered[u as usize] && clique[1..].iter().all(|&w| graph.has_edge(u, w)) {
+                clique.push(u);
+            }
+        }
+        clique.iter().for_each(|&u| covered[u as usize] = true);
+    }
+    bound
+}
+
+/// Returns up to `limit` distinct independent sets weighing more than one
+/// under `weights`, heaviest first, out of the maximal sets grown greedily in
+/// decreasing weight order after starting from each vertex in turn. Takes
+/// O(n (n + m)) time.
+fn heavy_independent_sets(graph: &Graph, weights: &[f64], limit: usize) -> Vec<Vec<Vertex>> {
+    let n = graph.nvertices();
+    let mut order: Vec<Vertex> = (0..n as Vertex).collect();
+    order.sort_by(|&a, &b| weights[b as usize].total_cmp(&weights[a as usize]));
+    let mut sets: Vec<(f64, Vec<Vertex>)> = (0..n as Vertex)
+        .into_par_iter()
+        .filter_map(|first| {
+            let mut blocked = vec![false; n];
+            let mut set = Vec::new();
+            let mut weight = 0.0;
+            for v in iter::once(first).chain(order.iter().copied()) {
+                if blocked[v as usize] {
+                    continue;
+                }
+                set.push(v);
+                weight += weights[v as usize];
+                blocked[v as usize] = true;
+                for &w in graph.neighbors(v) {
+                    blocked[w as usize] = true;
+                }
+            }
+            set.sort_unstable();
+            Some((weight, set)).filter(|&(weight, _)| weight > 1.0 + 1e-9)
+        })
+        .collect();
+    sets.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    sets.dedup_by(|a, b| a.1 == b.1);
+    sets.into_iter().take(limit).map(|(_, set)| set).collect()
+}
+
+/// Searches for a proper coloring with at most `k` colors, giving up after
+/// `time_limit`. Returns `None` if the graph isn't `k`-colorable or the
+/// search timed out.
+///
+/// Two colors are decided directly with [`is_bipartite`]. Otherwise each
+/// connected component is searched in parallel by backtracking, which picks
+/// the uncolored vertex with the fewest viable colors next and prunes as soon
+/// as some neighbor runs out of them. The search is exponential in the worst
+/// case, so this is meant for small graphs.
+pub fn is_k_colorable(graph: &Graph, k: u32, time_limit: Duration) -> Option<Vec<u32>> {
+    let start = Instant::now();
+    let deadline = start + time_limit;
+    if k == 2 {
+        return is_bipartite(graph).map(|[_, right]| {
+            let mut colors = vec![0; graph.nvertices()];
+            right.into_iter().for_each(|v| colors[v as usize] = 1);
+            colors
+        });
+    }
+
+    let labels = connected_components(graph);
+    let ncomponents = labels.iter().map(|&c| c as usize + 1).max().unwrap_or(0);
+    let mut components = vec![Vec::new(); ncomponents];
+    for (v, &c) in labels.iter().enumerate() {
+        components[c as usize].push(v as Vertex);
+    }
+    let component_colors: Option<Vec<Vec<u32>>> = components
+        .par_iter()
+        .map(|vertices| {
+            let subgraph = graph.induced_subgraph(vertices);
+            KColoringSearch::new(&subgraph, k, deadline).run()
+        })
+        .collect();
+
+    let colors = component_colors.map(|component_colors| {
+        let mut colors = vec![0; graph.nvertices()];
+        for (vertices, sub_colors) in components.iter().zip(component_colors) {
+            for (&v, c) in vertices.iter().zip(sub_colors) {
+                colors[v as usize] = c;
+            }
+        }
+        colors
+    });
+    println!(
+        "{}",
+        json!({
+            "k": k,
+            "k_colorable": colors.is_some(),
+            "k_colorable_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    colors
+}
+
+/// Backtracking state for [`is_k_colorable`] on a single component.
+struct KColoringSearch<'a> {
+    graph: &'a Graph,
+    colors: Vec<u32>,
+    ncolored: usize,
+    /// Colors not yet used by any colored neighbor, per vertex.
+    viable: Vec<DiscreteSampler>,
+    /// Every `(vertex, color)` pruned from `viable`, in order, for undoing.
+    trail: Vec<(Vertex, u32)>,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl<'a> KColoringSearch<'a> {
+    const NO_COLOR: u32 = u32::MAX;
+
+    fn new(graph: &'a Graph, k: u32, deadline: Instant) -> Self {
+        Self {
+            graph,
+            colors: vec![Self::NO_COLOR; graph.nvertices()],
+            ncolored: 0,
+            viable: (0..graph.nvertices())
+                .map(|_| DiscreteSampler::new(k))
+                .collect(),
+            trail: Vec::new(),
+            deadline,
+            timed_out: false,
+        }
+    }
+
+    fn run(mut self) -> Option<Vec<u32>> {
+        if self.extend(0) {
+            Some(self.colors)
+        } else {
+            None
+        }
+    }
+
+    /// Tries to color all remaining vertices, given that colors `0..nused`
+    /// are in use. Colors above `nused` are interchangeable, so only the
+    /// first of them is tried.
+    fn extend(&mut self, nused: u32) -> bool {
+        if self.ncolored == self.colors.len() {
+            return true;
+        }
+        if Instant::now() > self.deadline {
+            self.timed_out = true;
+            return false;
+        }
+
+        let v = (0..self.colors.len())
+            .filter(|&v| self.colors[v] == Self::NO_COLOR)
+            .min_by_key(|&v| {
+                (
+                    self.viable[v].nalive(),
+                    Reverse(self.graph.degree(v as Vertex)),
+                )
+            })
+            .unwrap();
+        let mut candidates: Vec<u32> = self.viable[v]
+            .alive_set
+            .iter()
+            .copied()
+            .filter(|&c| c <= nused)
+            .collect();
+        candidates.sort_unstable();
+
+        for c in candidates {
+            let mark = self.trail.len();
+            self.colors[v] = c;
+            self.ncolored += 1;
+            if self.prune(v as Vertex, c) && self.extend(nused.max(c + 1)) {
+                return true;
+            }
+            while self.trail.len() > mark {
+                let (w, c) = self.trail.pop().unwrap();
+                self.viable[w as usize].insert(c);
+            }
+            self.colors[v] = Self::NO_COLOR;
+            self.ncolored -= 1;
+            if self.timed_out {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Removes `c` from the viable colors of `v`'s uncolored neighbors,
+    /// returning false if one of them is left without any.
+    fn prune(&mut self, v: Vertex, c: u32) -> bool {
+        for &w in self.graph.neighbors(v) {
+            let viable = &mut self.viable[w as usize];
+            if self.colors[w as usize] != Self::NO_COLOR || !viable.alive[c as usize] {
+                continue;
+            }
+            viable.remove(c);
+            self.trail.push((w, c));
+            if viable.nalive() == 0 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Recommends a number of Glauber samples for `ncolors` colors from
+/// Jerrum's mixing time bound, `n ln n (k - Δ) / (k - 2Δ)` single-vertex
+/// updates for `k > 2Δ` colors on a graph with max degree `Δ`.
+///
+/// With fewer colors the bound doesn't apply; this logs a warning and
+/// returns the estimate for `k = 2Δ + 1` instead, which is only a guess.
+pub fn auto_nsamples(graph: &Graph, ncolors: u32) -> usize {
+    let n = graph.nvertices() as f64;
+    let max_degree = graph.max_degree() as f64;
+    let k = ncolors as f64;
+    let slack = if k > 2.0 * max_degree {
+        (k - max_degree) / (k - 2.0 * max_degree)
+    } else {
+        println!(
+            "{}",
+            json!({
+                "warning": "ncolors below 2 * max_degree + 1, mixing bound does not hold",
+                "ncolors": ncolors,
+                "max_degree": graph.max_degree(),
+            })
+        );
+        max_degree + 1.0
+    };
+    (n * n.ln().max(1.0) * slack).ceil() as usize
+}
+
+/// Return Glauber coloring after this many samples, along with the number of
+/// distinct colors it actually uses, which is at most `ncolors`.
+///
+/// Log out the intermediate colorings every `frequency` samples, along with the elapsed time.
+///
+/// Each sampler thread's generator is seeded from `seed` and its thread index,
+/// so runs are reproducible with a single thread. With several threads, which
+/// updates win their locks still depends on scheduling.
+pub fn glauber(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    frequency: usize,
+    out: &Path,
+    out_times: &Path,
+    seed: u64,
+) -> (Vec<u32>, u32) {
+    let colors = glauber_with_rng(
+        graph,
+        ncolors,
+        nsamples,
+        frequency,
+        out,
+        out_times,
+        seed,
+        default_rng_factory,
+    );
+    let actual_ncolors = actual_ncolors(&colors);
+    (colors, actual_ncolors)
+}
+
+/// The number of distinct colors in `colors`.
+fn actual_ncolors(colors: &[u32]) -> u32 {
+    colors.iter().collect::<HashSet<_>>().len() as u32
+}
+
+/// Runs `nsamples` Glauber steps which only recolor `affected_vertices` and
+/// their neighbors, starting from the proper coloring `colors`, which is
+/// updated in place and stays proper. This is meant for touching up a
+/// coloring after small local changes to the graph.
+///
+/// Panics if `colors` isn't a proper coloring with `ncolors` colors. The
+/// region is usually small, so the chain runs on a single thread.
+pub fn local_glauber(
+    graph: &Graph,
+    colors: &mut [u32],
+    ncolors: u32,
+    affected_vertices: &[Vertex],
+    nsamples: usize,
+    seed: u64,
+) {
+    assert!(
+        colors.len() == graph.nvertices(),
+        "{} colors for {} vertices",
+        colors.len(),
+        graph.nvertices()
+    );
+    assert!(
+        colors.iter().all(|&c| c < ncolors),
+        "colors outside of {} color budget",
+        ncolors
+    );
+    if let Err(conflicts) = verify_proper_coloring(graph, colors) {
+        panic!("improper coloring, conflicting edges {:?}", conflicts);
+    }
+
+    let mut region: Vec<Vertex> = affected_vertices
+        .iter()
+        .flat_map(|&v| iter::once(v).chain(graph.neighbors(v).iter().copied()))
+        .collect();
+    region.sort_unstable();
+    region.dedup();
+    if region.is_empty() {
+        return;
+    }
+
+    let start = Instant::now();
+    let mut state = SamplerThreadState::new(seed, ncolors);
+    for _ in 0..nsamples {
+        let v = region[state.rng.gen_range(0..region.len())];
+        state.viable_colors.reset();
+        for &w in graph.neighbors(v) {
+            state.viable_colors.remove(colors[w as usize]);
+        }
+        colors[v as usize] = state.viable_colors.sample(&mut state.rng);
+    }
+    debug_assert!(verify_proper_coloring(graph, colors).is_ok());
+
+    println!(
+        "{}",
+        json!({
+            "local_glauber_region": region.len(),
+            "nsamples": nsamples,
+            "local_glauber_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+}
+
+/// A random number generator which can drive a Glauber sampler thread.
+///
+/// Every `Send` generator implements this, so `Box<dyn GlauberRng>` can hold
+/// any of them. It builds on [`RngCore`], rather than [`Rng`], which isn't
+/// object safe, but boxed generators still get all of [`Rng`]'s methods.
+pub trait GlauberRng: RngCore + Send {}
+
+impl<R: RngCore + Send> GlauberRng for R {}
+
+/// The generator [`glauber`] uses for the sampler thread with the given seed.
+pub fn default_rng_factory(seed: u64) -> Box<dyn GlauberRng> {
+    Box::new(seeded_rng(seed))
+}
+
+/// Like [`glauber`], but each sampler thread draws from
+/// `rng_factory(thread_seed)`, where the thread seeds are derived from `seed`.
+#[allow(clippy::too_many_arguments)]
+pub fn glauber_with_rng<G>(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    frequency: usize,
+    out: &Path,
+    out_times: &Path,
+    seed: u64,
+    rng_factory: G,
+) -> Vec<u32>
+where
+    G: Fn(u64) -> Box<dyn GlauberRng> + Sync,
+{
+    let logger = Mutex::new(GlauberLogger::new(out, out_times));
+    observe_with_rng(
+        graph,
+        ncolors,
+        nsamples,
+        frequency,
+        seed,
+        rng_factory,
+        |step, colors| logger.lock().unwrap().log(step, colors),
+    )
+}
+
+/// Return Glauber coloring after this many samples, calling `observe` with
+/// `(step, colors)` for the initial greedy coloring at step 0 and then after
+/// every further `frequency` samples, with a possibly shorter final batch.
+///
+/// `observe` is only invoked from the calling thread while the sampler threads
+/// are idle, so it never runs concurrently with itself or with any color
+/// updates and `colors` is always a consistent proper coloring. Since it must
+/// be `Fn + Sync`, any state it accumulates needs interior mutability, e.g., a
+/// `Mutex`. Time spent in `observe` isn't counted in the logged times.
+pub fn glauber_observe<F>(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    frequency: usize,
+    seed: u64,
+    observe: F,
+) -> Vec<u32>
+where
+    F: Fn(usize, &[u32]) + Sync,
+{
+    observe_with_rng(
+        graph,
+        ncolors,
+        nsamples,
+        frequency,
+        seed,
+        default_rng_factory,
+        observe,
+    )
+}
+
+fn observe_with_rng<G, F>(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    frequency: usize,
+    seed: u64,
+    rng_factory: G,
+    observe: F,
+) -> Vec<u32>
+where
+    G: Fn(u64) -> Box<dyn GlauberRng> + Sync,
+    F: Fn(usize, &[u32]) + Sync,
+{
+    let (greedy_ncolors, initial) = greedy(graph, VertexOrdering::DegreeLargestFirst);
+    assert!(
+        greedy_ncolors <= ncolors,
+        "greedy ncolors {} budget {}",
+        greedy_ncolors,
+        ncolors
+    );
+
+    // https://www.math.cmu.edu/~af1p/Texfiles/colorbdd.pdf
+    // https://www.math.cmu.edu/~af1p/Teaching/MCC17/Papers/colorJ.pdf
+    // run glauber markov chain on a coloring
+    // chain sampling can be parallel with some simple conflict detection
+
+    let nthreads = rayon::current_num_threads();
+    let mut thread_states: Vec<_> = (0..nthreads)
+        .map(|i| SamplerThreadState::with_rng(rng_factory(thread_seed(seed, i)), ncolors))
+        .collect();
+    let run = run_chain(
+        graph,
+        initial,
+        Budget::Samples(nsamples),
+        frequency,
+        &mut thread_states,
+        observe,
+    );
+
+    println!(
+        "{}",
+        json!({
+            "greedy_ncolors": greedy_ncolors,
+            "glauber_ncolors": ncolors,
+            "actual_ncolors": actual_ncolors(&run.colors),
+            "nsamples": nsamples,
+            "conflicts": run.conflicts,
+            "nthreads": nthreads,
+            "conflict_percent": 100.0 * run.conflicts as f64 / (nsamples + run.conflicts) as f64,
+            "steps": run.steps_history,
+            "times": run.times_history,
+        })
+    );
+
+    run.colors
+}
+
+/// Return Glauber coloring after running the chain for about `duration` of
+/// wall-clock time, stopping at the first multiple of `frequency` samples past
+/// the deadline.
+///
+/// The number of samples actually taken is reported in the JSON log.
+pub fn glauber_timed(
+    graph: &Graph,
+    ncolors: u32,
+    duration: Duration,
+    frequency: usize,
+    seed: u64,
+) -> Vec<u32> {
+    assert!(frequency > 0, "frequency must be positive");
+    let (greedy_ncolors, initial) = greedy(graph, VertexOrdering::DegreeLargestFirst);
+    assert!(
+        greedy_ncolors <= ncolors,
+        "greedy ncolors {} budget {}",
+        greedy_ncolors,
+        ncolors
+    );
+
+    let nthreads = rayon::current_num_threads();
+    let mut thread_states: Vec<_> = (0..nthreads)
+        .map(|i| SamplerThreadState::new(thread_seed(seed, i), ncolors))
+        .collect();
+    let run = run_chain(
+        graph,
+        initial,
+        Budget::Time(duration),
+        frequency,
+        &mut thread_states,
+        |_, _| {},
+    );
+    let nsamples = *run.steps_history.last().unwrap();
+
+    println!(
+        "{}",
+        json!({
+            "greedy_ncolors": greedy_ncolors,
+            "glauber_ncolors": ncolors,
+            "duration": format!("{:.0?}", duration),
+            "nsamples": nsamples,
+            "conflicts": run.conflicts,
+            "nthreads": nthreads,
+            "conflict_percent": 100.0 * run.conflicts as f64 / (nsamples + run.conflicts).max(1) as f64,
+            "steps": run.steps_history,
+            "times": run.times_history,
+        })
+    );
+
+    run.colors
+}
+
+/// Return Glauber coloring after this many samples, starting the chain from
+/// `initial_colors` instead of a greedy coloring, e.g., to continue from the
+/// result of an earlier run.
+///
+/// Panics if `initial_colors` has colors outside the `ncolors` budget, and,
+/// in debug builds, if it isn't a proper coloring.
+pub fn glauber_warm(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    initial_colors: Vec<u32>,
+    seed: u64,
+) -> Vec<u32> {
+    assert!(
+        initial_colors.len() == graph.nvertices(),
+        "{} initial colors for {} vertices",
+        initial_colors.len(),
+        graph.nvertices()
+    );
+    assert!(
+        initial_colors.iter().all(|&c| c < ncolors),
+        "initial colors outside of {} color budget",
+        ncolors
+    );
+    debug_assert!(
+        verify_proper_coloring(graph, &initial_colors).is_ok(),
+        "improper initial coloring"
+    );
+
+    let nthreads = rayon::current_num_threads();
+    let mut thread_states: Vec<_> = (0..nthreads)
+        .map(|i| SamplerThreadState::new(thread_seed(seed, i), ncolors))
+        .collect();
+    let run = run_chain(
+        graph,
+        initial_colors,
+        Budget::Samples(nsamples),
+        nsamples.max(1),
+        &mut thread_states,
+        |_, _| {},
+    );
+
+    println!(
+        "{}",
+        json!({
+            "glauber_ncolors": ncolors,
+            "nsamples": nsamples,
+            "conflicts": run.conflicts,
+            "nthreads": nthreads,
+            "conflict_percent": 100.0 * run.conflicts as f64 / (nsamples + run.conflicts).max(1) as f64,
+            "times": run.times_history,
+        })
+    );
+
+    run.colors
+}
+
+/// Return a coloring after `nsamples` Metropolis-Hastings steps targeting the
+/// distribution over proper colorings proportional to `exp(log_weight(colors))`.
+///
+/// Each step picks a uniform vertex and proposes a uniform color among those
+/// not used by its neighbors. Since the vertex's viable colors don't depend
+/// on its own color, the proposal is symmetric, so it's accepted with
+/// probability `min(1, exp(log_weight(proposed) - log_weight(current)))`.
+///
+/// Because `log_weight` sees the whole coloring, this chain is sequential.
+pub fn glauber_mh<W>(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples: usize,
+    seed: u64,
+    log_weight: W,
+) -> Vec<u32>
+where
+    W: Fn(&[u32]) -> f64 + Sync,
+{
+    let (greedy_ncolors, mut colors) = greedy(graph, VertexOrdering::DegreeLargestFirst);
+    assert!(
+        greedy_ncolors <= ncolors,
+        "greedy ncolors {} budget {}",
+        greedy_ncolors,
+        ncolors
+    );
+
+    let mut rng = seeded_rng(seed);
+    let mut viable_colors = DiscreteSampler::new(ncolors);
+    let mut current = log_weight(&colors);
+    let mut accepted = 0;
+    let start = Instant::now();
+    for _ in 0..nsamples {
+        let v = rng.gen_range(0..(graph.nvertices() as u32)) as usize;
+        viable_colors.reset();
+        for &w in graph.neighbors(v as Vertex) {
+            viable_colors.remove(colors[w as usize]);
+        }
+        let previous = colors[v];
+        colors[v] = viable_colors.sample(&mut rng);
+        if colors[v] == previous {
+            accepted += 1;
+            continue;
+        }
+        let proposed = log_weight(&colors);
+        if rng.gen::<f64>().ln() < proposed - current {
+            current = proposed;
+            accepted += 1;
+        } else {
+            colors[v] = previous;
+        }
+    }
+
+    println!(
+        "{}",
+        json!({
+            "greedy_ncolors": greedy_ncolors,
+            "glauber_ncolors": ncolors,
+            "nsamples": nsamples,
+            "accept_percent": 100.0 * accepted as f64 / nsamples.max(1) as f64,
+            "mh_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+
+    colors
+}
+
+/// The outcome of [`glauber_multi_chain`].
+pub struct MultiChainResult {
+    /// Final coloring of the best chain, which ended up using the fewest
+    /// colors, then ran into the fewest conflicts.
+    pub colors: Vec<u32>,
+    /// Index of the chain `colors` came from, the lowest among equally good
+    /// chains.
+    pub best_chain: usize,
+    /// Number of distinct colors in each chain's final coloring.
+    pub ncolors_used: Vec<u32>,
+    /// Total conflicts encountered by each chain.
+    pub conflicts: Vec<usize>,
+    /// Gelman-Rubin potential scale reduction factor for each vertex, where
+    /// values near 1 suggest the chains agree.
+    pub rhat: Vec<f64>,
+    /// Median-of-means estimate for each vertex of how often it has its
+    /// starting color: the median over chains of each chain's fraction of
+    /// kept snapshots in which it does.
+    pub unchanged_fraction: Vec<f64>,
+}
+
+/// Number of snapshots each chain in [`glauber_multi_chain`] takes, of which
+/// the latter half are kept after burn-in.
+const MULTI_CHAIN_SNAPSHOTS: usize = 100;
+
+/// Runs `nchains` independent Glauber chains from the greedy coloring
+/// concurrently, each with its own seeds derived from `seed`. The current
+/// thread pool's threads are split evenly among the chains, with at least one
+/// sampler thread per chain, so chains can only run into conflicts when the
+/// pool has at least two threads per chain.
+///
+/// The Gelman-Rubin R-hat diagnostic is computed for each vertex over the
+/// indicator that the vertex still has its starting color, using the latter
+/// half of regularly spaced snapshots from every chain. The same per-chain
+/// means are aggregated into [`MultiChainResult::unchanged_fraction`] by
+/// taking their median, which a single stuck chain can't drag away.
+pub fn glauber_multi_chain(
+    graph: &Graph,
+    ncolors: u32,
+    nsamples_per_chain: usize,
+    nchains: usize,
+    seed: u64,
+) -> MultiChainResult {
+    assert!(nchains >= 2, "need at least two chains, got {}", nchains);
+    let (greedy_ncolors, initial) = greedy(graph, VertexOrdering::DegreeLargestFirst);
+    assert!(
+        greedy_ncolors <= ncolors,
+        "greedy ncolors {} budget {}",
+        greedy_ncolors,
+        ncolors
+    );
+
+    let frequency = (nsamples_per_chain / MULTI_CHAIN_SNAPSHOTS).max(1);
+    let burn_in = nsamples_per_chain / 2;
+    let nthreads = (rayon::current_num_threads() / nchains).max(1);
+    let initial = &initial;
+
+    // each chain tallies how many kept snapshots had each vertex at its
+    // initial color, along with the number of kept snapshots
+    let runs: Vec<(ChainRun, Vec<u32>, u32)> = (0..nchains)
+        .into_par_iter()
+        .map(|chain| {
+            let mut thread_states: Vec<_> = (0..nthreads)
+                .map(|i| SamplerThreadState::new(thread_seed(seed, chain * nthreads + i), ncolors))
+                .collect();
+            let mut unchanged = vec![0u32; graph.nvertices()];
+            let mut kept = 0;
+            let run = run_chain(
+                graph,
+                initial.clone(),
+                Budget::Samples(nsamples_per_chain),
+                frequency,
+                &mut thread_states,
+                |step, colors| {
+                    if step < burn_in {
+                        return;
+                    }
+                    kept += 1;
+                    for ((u, &c), &c0) in unchanged.iter_mut().zip(colors).zip(initial) {
+                        *u += (c == c0) as u32;
+                    }
+                },
+            );
+            (run, unchanged, kept)
+        })
+        .collect();
+
+    let rhat: Vec<f64> = (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let chains = runs.iter().map(|(_, unchanged, kept)| {
+                let n = f64::from(*kept);
+                let mean = f64::from(unchanged[v]) / n;
+                // sample variance of a binary indicator series
+                let var = if *kept > 1 {
+                    n / (n - 1.0) * mean * (1.0 - mean)
+                } else {
+                    0.0
+                };
+                (mean, var)
+            });
+            gelman_rubin_rhat(chains, f64::from(runs[0].2))
+        })
+        .collect();
+
+    let unchanged_fraction: Vec<f64> = (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let mut means: Vec<f64> = runs
+                .iter()
+                .map(|(_, unchanged, kept)| f64::from(unchanged[v]) / f64::from(*kept))
+                .collect();
+            means.sort_unstable_by(f64::total_cmp);
+            let mid = means.len() / 2;
+            if means.len().is_multiple_of(2) {
+                (means[mid - 1] + means[mid]) / 2.0
+            } else {
+                means[mid]
+            }
+        })
+        .collect();
+
+    let ncolors_used: Vec<u32> = runs
+        .iter()
+        .map(|(run, _, _)| actual_ncolors(&run.colors))
+        .collect();
+    let conflicts: Vec<usize> = runs.iter().map(|(run, _, _)| run.conflicts).collect();
+    let best_chain = (0..nchains)
+        .min_by_key(|&c| (ncolors_used[c], conflicts[c]))
+        .unwrap();
+
+    println!(
+        "{}",
+        json!({
+            "greedy_ncolors": greedy_ncolors,
+            "glauber_ncolors": ncolors,
+            "nsamples_per_chain": nsamples_per_chain,
+            "nchains": nchains,
+            "nthreads_per_chain": nthreads,
+            "ncolors_used": ncolors_used,
+            "conflicts": conflicts,
+            "best_chain": best_chain,
+            "max_rhat": rhat.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            "unmixed_vertices": rhat.iter().filter(|&&r| r > 1.01).count(),
+        })
+    );
+
+    MultiChainResult {
+        colors: runs.into_iter().nth(best_chain).unwrap().0.colors,
+        best_chain,
+        ncolors_used,
+        conflicts,
+        rhat,
+        unchanged_fraction,
+    }
+}
+
+/// Returns the Gelman-Rubin R-hat of the number of vertices with color 0 over
+/// the snapshots of each chain, which should all be the same length. Values
+/// below about 1.01 suggest the chains have converged.
+pub fn gelman_rubin(chains: &[Vec<Vec<u32>>]) -> f64 {
+    assert!(
+        chains.len() >= 2,
+        "need at least two chains, got {}",
+        chains.len()
+    );
+    let n = chains[0].len();
+    assert!(n >= 2, "need at least two snapshots per chain, got {}", n);
+    assert!(
+        chains.iter().all(|chain| chain.len() == n),
+        "chains have differing lengths"
+    );
+    let moments: Vec<(f64, f64)> = chains
+        .iter()
+        .map(|chain| {
+            let series = color_zero_counts(chain);
+            let mean = series.iter().sum::<f64>() / n as f64;
+            let var = series.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            (mean, var)
+        })
+        .collect();
+    gelman_rubin_rhat(moments.into_iter(), n as f64)
+}
+
+/// Computes R-hat from the `(mean, sample variance)` of each chain, where every
+/// chain has `n` draws.
+///
+/// If all chains are constant this is 1 when they agree and infinite otherwise.
+fn gelman_rubin_rhat(chains: impl Iterator<Item = (f64, f64)> + Clone, n: f64) -> f64 {
+    let m = chains.clone().count() as f64;
+    let grand_mean = chains.clone().map(|(mean, _)| mean).sum::<f64>() / m;
+    let between = n / (m - 1.0)
+        * chains
+            .clone()
+            .map(|(mean, _)| (mean - grand_mean).powi(2))
+            .sum::<f64>();
+    let within = chains.map(|(_, var)| var).sum::<f64>() / m;
+    if within == 0.0 {
+        return if between == 0.0 { 1.0 } else { f64::INFINITY };
+    }
+    let pooled = (n - 1.0) / n * within + between / n;
+    (pooled / within).sqrt()
+}
+
+/// Estimates the effective number of independent samples among the snapshots
+/// of a single chain, `n / (1 + 2 * sum_lag rho(lag))`.
+///
+/// The autocorrelation `rho` at each lag is averaged over the indicators that
+/// each vertex has its color from the first snapshot, skipping vertices whose
+/// indicator never changes, and the sum stops at the first lag where it is no
+/// longer positive. A chain where no vertex ever changes color counts as one
+/// sample.
+pub fn effective_sample_size(snapshots: &[Vec<u32>]) -> f64 {
+    let n = snapshots.len();
+    if n < 2 {
+        return n as f64;
+    }
+    let nvertices = snapshots[0].len();
+    let indicator = |t: usize, v: usize| (snapshots[t][v] == snapshots[0][v]) as u32 as f64;
+
+    // (vertex, mean, variance) over the varying vertices
+    let moments: Vec<(usize, f64, f64)> = (0..nvertices)
+        .into_par_iter()
+        .filter_map(|v| {
+            let mean = (0..n).map(|t| indicator(t, v)).sum::<f64>() / n as f64;
+            let var = mean * (1.0 - mean);
+            if var > 0.0 {
+                Some((v, mean, var))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if moments.is_empty() {
+        return 1.0;
+    }
+
+    let mut rho_sum = 0.0;
+    for lag in 1..n {
+        let rho = moments
+            .par_iter()
+            .map(|&(v, mean, var)| {
+                (0..n - lag)
+                    .map(|t| (indicator(t, v) - mean) * (indicator(t + lag, v) - mean))
+                    .sum::<f64>()
+                    / (n as f64 * var)
+            })
+            .sum::<f64>()
+            / moments.len() as f64;
+        if rho <= 0.0 {
+            break;
+        }
+        rho_sum += rho;
+    }
+    n as f64 / (1.0 + 2.0 * rho_sum)
+}
+
+/// Returns the autocorrelation at lags `0..=max_lag` of the number of
+/// vertices with color 0 in each snapshot, normalized by its lag-0 variance.
+///
+/// Constant summaries, such as from a repeated coloring, are perfectly
+/// correlated at every lag.
+pub fn autocorrelation(snapshots: &[Vec<u32>], max_lag: usize) -> Vec<f64> {
+    let n = snapshots.len();
+    assert!(max_lag < n, "max lag {} for {} snapshots", max_lag, n);
+    let series = color_zero_counts(snapshots);
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let centered: Vec<f64> = series.iter().map(|x| x - mean).collect();
+    let var = centered.iter().map(|x| x * x).sum::<f64>();
+    if var == 0.0 {
+        return vec![1.0; max_lag + 1];
+    }
+    (0..=max_lag)
+        .map(|lag| {
+            centered
+                .iter()
+                .zip(&centered[lag..])
+                .map(|(x, y)| x * y)
+                .sum::<f64>()
+                / var
+        })
+        .collect()
+}
 
-    const NO_COLOR: u32 = std::u32::MAX;
-    let mut colors: Vec<u32> = vec![NO_COLOR; nvertices];
-    let mut adjacent_colors: Vec<bool> = Vec::new();
+/// The number of vertices with color 0 in each snapshot, as a scalar summary
+/// of a chain.
+fn color_zero_counts(snapshots: &[Vec<u32>]) -> Vec<f64> {
+    snapshots
+        .par_iter()
+        .map(|colors| colors.iter().filter(|&&c| c == 0).count() as f64)
+        .collect()
+}
 
-    let greedy_start = Instant::now();
-    for vertex in vertices.into_iter().rev() {
-        // loop invariant is that none of adjacent_colors elements are true
+struct ChainRun {
+    colors: Vec<u32>,
+    conflicts: usize,
+    steps_history: Vec<usize>,
+    times_history: Vec<f64>,
+}
 
-        // what color are our neighbors?
-        let mut nadjacent_colors = 0;
-        for &n in graph.neighbors(vertex) {
-            let n = n as usize;
-            if colors[n] == NO_COLOR {
-                continue;
-            }
+/// When to stop a chain.
+enum Budget {
+    /// Stop after exactly this many steps.
+    Samples(usize),
+    /// Stop at the first `frequency` boundary once this much wall-clock time
+    /// has passed.
+    Time(Duration),
+}
 
-            let c = colors[n] as usize;
-            if !adjacent_colors[c] {
-                adjacent_colors[c] = true;
-                nadjacent_colors += 1;
-                if nadjacent_colors == colors.len() {
-                    break;
-                }
-            }
-        }
+/// Runs the Glauber chain from the `initial` coloring until the budget runs
+/// out, across the given thread states, calling `observe` with a snapshot at
+/// step 0 and after every `frequency` steps.
+///
+/// The recorded times only include time spent sampling.
+fn run_chain<F>(
+    graph: &Graph,
+    initial: Vec<u32>,
+    budget: Budget,
+    frequency: usize,
+    thread_states: &mut [SamplerThreadState],
+    mut observe: F,
+) -> ChainRun
+where
+    F: FnMut(usize, &[u32]),
+{
+    let mut snapshot = initial;
+    let mut colors = snapshot
+        .iter()
+        .copied()
+        .map(AlignedRwu32::new)
+        .collect::<Vec<_>>();
 
-        // what's the smallest color not in our neighbors?
-        let chosen = if nadjacent_colors == adjacent_colors.len() {
-            adjacent_colors.push(false);
-            adjacent_colors.len() - 1
-        } else {
-            adjacent_colors.iter().copied().position(|x| !x).unwrap()
-        };
-        colors[vertex as usize] = chosen as u32;
+    let mut steps = 0;
+    let mut elapsed_seconds = 0.0;
+    let mut steps_history = vec![steps];
+    let mut times_history = vec![elapsed_seconds];
+    observe(steps, &snapshot);
 
-        // retain loop invariant, unset neighbor colors
-        if graph.degree(vertex) >= adjacent_colors.len() {
-            graph
-                .neighbors(vertex)
-                .iter()
-                .map(|&n| colors[n as usize])
-                .filter(|&n| n != NO_COLOR)
-                .for_each(|c| {
-                    adjacent_colors[c as usize] = false;
-                });
-        } else {
-            for c in adjacent_colors.iter_mut() {
-                *c = false;
+    let start = Instant::now();
+    let mut conflicts: usize = 0;
+    loop {
+        let samples_to_sample = match budget {
+            Budget::Samples(nsamples) => frequency.min(nsamples - steps),
+            Budget::Time(duration) => {
+                if Instant::now().duration_since(start) >= duration {
+                    0
+                } else {
+                    frequency
+                }
             }
+        };
+        if samples_to_sample == 0 {
+            break;
         }
-    }
-    let greedy_time = format!("{:.0?}", Instant::now().duration_since(greedy_start));
+        let round_start = Instant::now();
+        conflicts += sample_round(graph, &colors, thread_states, samples_to_sample, None);
+        elapsed_seconds += Instant::now().duration_since(round_start).as_secs_f64();
+        steps += samples_to_sample;
+        steps_history.push(steps);
+        times_history.push(elapsed_seconds);
 
-    let ncolors = adjacent_colors.len();
-
-    println!(
-        "{}",
-        json!({
-            "vertex_sort_time": sort_time,
-            "greedy_color_time": greedy_time,
-            "greedy_ncolors": ncolors,
-        })
-    );
+        snapshot
+            .iter_mut()
+            .zip(colors.iter_mut())
+            .for_each(|(s, c)| *s = c.mut_read());
+        observe(steps, &snapshot);
+    }
 
-    (ncolors as u32, colors)
+    ChainRun {
+        colors: AlignedRwu32::drain_unchecked(colors),
+        conflicts,
+        steps_history,
+        times_history,
+    }
 }
 
-/// Return Glauber coloring after this many samples, as well as the time that
-/// it took to get to each extra `frequency` number of sampling steps.
+/// Return Glauber coloring after this many samples, along with the elapsed
+/// time since the start of the chain at which each individual step completed.
 ///
-/// Log out the intermediate colorings every `frequency` samples, along with the elapsed time.
-pub fn glauber(
+/// Since steps are taken in parallel, the `i`-th duration is the time at which
+/// `i + 1` steps had completed, so the durations are sorted.
+pub fn glauber_with_timestamps(
     graph: &Graph,
     ncolors: u32,
     nsamples: usize,
-    frequency: usize,
-    out: &Path,
-    out_times: &Path,
-    seed: usize,
-) -> Vec<u32> {
-    let (greedy_ncolors, colors) = greedy(graph);
+    seed: u64,
+) -> (Vec<u32>, Vec<Duration>) {
+    let (greedy_ncolors, colors) = greedy(graph, VertexOrdering::DegreeLargestFirst);
     assert!(
         greedy_ncolors <= ncolors,
         "greedy ncolors {} budget {}",
@@ -133,60 +1722,24 @@ pub fn glauber(
         ncolors
     );
 
-    // https://www.math.cmu.edu/~af1p/Texfiles/colorbdd.pdf
-    // https://www.math.cmu.edu/~af1p/Teaching/MCC17/Papers/colorJ.pdf
-    // run glauber markov chain on a coloring
-    // chain sampling can be parallel with some simple conflict detection
-
-    let mut colors = colors.into_iter().map(Rwu32::new).collect::<Vec<_>>();
-    let nthreads = rayon::current_num_threads() as usize;
-
-    let mut logger = GlauberLogger::new(out, out_times);
-    // contains f64 elapsed seconds, init to 0
-    // contains usize steps, init to 0
-    logger.log(&mut colors);
-
-    let mut conflicts: usize = 0;
-    let mut samples_left_this_round = AtomicI64::new(0);
+    let colors = colors
+        .into_iter()
+        .map(AlignedRwu32::new)
+        .collect::<Vec<_>>();
+    let nthreads = rayon::current_num_threads();
     let mut thread_states: Vec<_> = (0..nthreads)
-        .map(|i| SamplerThreadState::new(seed * nthreads + i, ncolors))
+        .map(|i| SamplerThreadState::new(thread_seed(seed, i), ncolors))
         .collect();
-    while logger.steps < nsamples.try_into().unwrap() {
-        let samples_to_sample = frequency.min(nsamples - logger.steps as usize);
-        *samples_left_this_round.get_mut() = samples_to_sample.try_into().unwrap();
-        logger.start();
-        conflicts += thread_states
-            .par_iter_mut()
-            .map(|state| {
-                // thread state, map over this, init'd outside of loop
-                let mut neighbor_guards = Vec::new();
-
-                let mut conflicts = 0;
-
-                while samples_left_this_round.fetch_sub(1, Ordering::Relaxed) > 0 {
-                    loop {
-                        let successful = try_mcmc_update(
-                            &mut state.rng,
-                            &colors,
-                            &graph,
-                            &mut state.viable_colors,
-                            &mut neighbor_guards,
-                        );
-                        neighbor_guards.clear();
-                        if successful.is_some() {
-                            break;
-                        }
-                        conflicts += 1;
-                    }
-                }
-                conflicts
-            })
-            .sum::<usize>();
-        logger.stop(samples_to_sample.try_into().unwrap());
-        logger.log(&mut colors);
-    }
 
-    let colors = colors.into_iter().map(|x| x.into_inner()).collect();
+    let start = Instant::now();
+    let conflicts = sample_round(graph, &colors, &mut thread_states, nsamples, Some(start));
+    let mut timestamps: Vec<Duration> = thread_states
+        .iter_mut()
+        .flat_map(|state| state.timestamps.drain(..))
+        .collect();
+    timestamps.sort_unstable();
+
+    let colors = AlignedRwu32::drain_unchecked(colors);
 
     println!(
         "{}",
@@ -197,19 +1750,130 @@ pub fn glauber(
             "conflicts": conflicts,
             "nthreads": nthreads,
             "conflict_percent": 100.0 * conflicts as f64 / (nsamples + conflicts) as f64,
-            "steps": logger.steps_history,
-            "times": logger.times_history,
+            "elapsed_seconds": timestamps.last().copied().unwrap_or_default().as_secs_f64(),
         })
     );
 
-    colors
+    (colors, timestamps)
+}
+
+/// Writes `(steps, colors)` snapshots in a little-endian binary format read by
+/// [`read_snapshots_bin`]: a `u64` vertex count followed by, for every
+/// snapshot, its `u64` step number and packed `u32` colors.
+///
+/// Snapshots are encoded in parallel and must all have the same number of
+/// vertices.
+pub fn write_snapshots_bin(snapshots: &[(usize, Vec<u32>)], path: &Path) -> io::Result<()> {
+    let nvertices = snapshots.first().map_or(0, |(_, colors)| colors.len());
+    assert!(
+        snapshots
+            .iter()
+            .all(|(_, colors)| colors.len() == nvertices),
+        "snapshots have differing vertex counts"
+    );
+    let encoded: Vec<Vec<u8>> = snapshots
+        .par_iter()
+        .map(|(steps, colors)| {
+            let mut buf = Vec::with_capacity(8 + 4 * colors.len());
+            buf.extend_from_slice(&(*steps as u64).to_le_bytes());
+            for c in colors {
+                buf.extend_from_slice(&c.to_le_bytes());
+            }
+            buf
+        })
+        .collect();
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&(nvertices as u64).to_le_bytes())?;
+    for buf in &encoded {
+        writer.write_all(buf)?;
+    }
+    writer.flush()
+}
+
+/// Reads snapshots written by [`write_snapshots_bin`].
+pub fn read_snapshots_bin(path: &Path) -> io::Result<Vec<(usize, Vec<u32>)>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+    if bytes.len() < 8 {
+        return Err(invalid("missing snapshot header"));
+    }
+    let (header, body) = bytes.split_at(8);
+    let nvertices = u64::from_le_bytes(header.try_into().unwrap()) as usize;
+    let record = 8 + 4 * nvertices;
+    if body.len() % record != 0 {
+        return Err(invalid("truncated snapshot"));
+    }
+    Ok(body
+        .par_chunks(record)
+        .map(|chunk| {
+            let (steps, colors) = chunk.split_at(8);
+            let steps = u64::from_le_bytes(steps.try_into().unwrap()) as usize;
+            let colors = colors
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+                .collect();
+            (steps, colors)
+        })
+        .collect())
+}
+
+/// Takes `nsamples` successful Glauber steps over `colors` in parallel, one
+/// task per thread state, returning the number of conflicts encountered.
+///
+/// Rather than splitting the steps evenly up front, each thread repeatedly
+/// claims the next step from a shared counter and retries it until it goes
+/// through, so threads slowed down by conflicts around high degree vertices
+/// just end up taking fewer of the steps.
+///
+/// If `clock` is provided, each thread state records the time elapsed since
+/// `clock` at which each of its steps completed.
+fn sample_round(
+    graph: &Graph,
+    colors: &[AlignedRwu32],
+    thread_states: &mut [SamplerThreadState],
+    nsamples: usize,
+    clock: Option<Instant>,
+) -> usize {
+    let claimed = AtomicUsize::new(0);
+    thread_states
+        .par_iter_mut()
+        .map(|state| {
+            // thread state, map over this, init'd outside of loop
+            let mut neighbor_guards = Vec::new();
+
+            let mut conflicts = 0;
+
+            while claimed.fetch_add(1, Ordering::Relaxed) < nsamples {
+                loop {
+                    let successful = try_mcmc_update(
+                        &mut state.rng,
+                        colors,
+                        graph,
+                        &mut state.viable_colors,
+                        &mut neighbor_guards,
+                    );
+                    neighbor_guards.clear();
+                    if successful.is_some() {
+                        break;
+                    }
+                    conflicts += 1;
+                }
+                if let Some(clock) = clock {
+                    state.timestamps.push(Instant::now().duration_since(clock));
+                }
+            }
+            conflicts
+        })
+        .sum::<usize>()
 }
 
 /// Crucially, only drop neighbor locks after vertex is updated.
 /// (whenever the parameter argument is cleared).
 fn try_mcmc_update<'a, R: Rng>(
     rng: &mut R,
-    colors: &'a [Rwu32],
+    colors: &'a [AlignedRwu32],
     graph: &Graph,
     viable_colors: &mut DiscreteSampler,
     neighbor_guards: &mut Vec<ReadGuard<'a>>,
@@ -230,7 +1894,7 @@ fn try_mcmc_update<'a, R: Rng>(
     }
 
     let chosen = viable_colors.sample(rng);
-    v_color_guard.write(chosen as u32);
+    v_color_guard.write(chosen);
 
     Some(())
 }
@@ -239,10 +1903,11 @@ fn try_mcmc_update<'a, R: Rng>(
 ///
 ///  - constant-time removal from domain
 ///  - constant-time sampling
-///  - (if it was to be implemented) constant-time insertion
+///  - constant-time insertion back into the domain
 struct DiscreteSampler {
     alive_set: Vec<u32>,
     dead_set: Vec<u32>,
+    /// Position of each element in whichever of the two sets holds it.
     index: Vec<u32>,
     alive: Vec<bool>,
 }
@@ -259,7 +1924,7 @@ impl DiscreteSampler {
     }
 
     /// No-op if `i` is dead.
-    fn remove(&mut self, i: u32) {
+    pub fn remove(&mut self, i: u32) {
         if !self.alive[i as usize] {
             return;
         }
@@ -267,11 +1932,25 @@ impl DiscreteSampler {
         self.index[*self.alive_set.last().unwrap() as usize] = ix;
         let ii = self.alive_set.swap_remove(ix as usize);
         assert!(ii == i);
-        self.index[i as usize] = std::u32::MAX;
+        self.index[i as usize] = self.dead_set.len() as u32;
         self.dead_set.push(i);
         self.alive[i as usize] = false;
     }
 
+    /// Re-adds a removed `i` to the domain. No-op if `i` is alive.
+    fn insert(&mut self, i: u32) {
+        if self.alive[i as usize] {
+            return;
+        }
+        let ix = self.index[i as usize];
+        self.index[*self.dead_set.last().unwrap() as usize] = ix;
+        let ii = self.dead_set.swap_remove(ix as usize);
+        assert!(ii == i);
+        self.index[i as usize] = self.alive_set.len() as u32;
+        self.alive_set.push(i);
+        self.alive[i as usize] = true;
+    }
+
     /// Reverts to original domain.
     fn reset(&mut self) {
         for i in self.dead_set.drain(..) {
@@ -291,15 +1970,139 @@ impl DiscreteSampler {
     }
 }
 
+/// A sampler over `0..n` proportional to fixed non-negative weights, which
+/// allows:
+///
+///  - constant-time sampling, with Walker's alias method
+///  - amortized constant-time removal from domain
+///  - linear-time reset to the original domain
+///
+/// Removed elements are rejected when drawn. Once they hold more than half of
+/// the mass the alias table was built over, it's rebuilt over the remaining
+/// elements, so sampling takes at most two draws in expectation.
+pub struct WeightedDiscreteSampler {
+    weights: Vec<f64>,
+    alive: Vec<bool>,
+    alive_weight: f64,
+    /// Total weight of the elements the current table was built with.
+    table_weight: f64,
+    prob: Vec<f64>,
+    alias: Vec<u32>,
+    /// Tables over the original domain, for resets.
+    full_prob: Vec<f64>,
+    full_alias: Vec<u32>,
+}
+
+impl WeightedDiscreteSampler {
+    /// Initializes a sampler over the entire domain `0..weights.len()`.
+    pub fn new(weights: Vec<f64>) -> Self {
+        assert!(
+            weights.iter().all(|&w| w >= 0.0 && w.is_finite()),
+            "weights must be finite and non-negative"
+        );
+        let alive = vec![true; weights.len()];
+        let (prob, alias) = Self::alias_table(&weights, &alive);
+        let total = weights.iter().sum();
+        Self {
+            alive,
+            alive_weight: total,
+            table_weight: total,
+            full_prob: prob.clone(),
+            full_alias: alias.clone(),
+            prob,
+            alias,
+            weights,
+        }
+    }
+
+    /// Builds Vose's alias tables over the weights of the alive elements.
+    fn alias_table(weights: &[f64], alive: &[bool]) -> (Vec<f64>, Vec<u32>) {
+        let n = weights.len();
+        let total: f64 = (0..n).filter(|&i| alive[i]).map(|i| weights[i]).sum();
+        let mut prob: Vec<f64> = (0..n)
+            .map(|i| {
+                if alive[i] && total > 0.0 {
+                    weights[i] * n as f64 / total
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let mut alias: Vec<u32> = (0..n as u32).collect();
+        let (mut small, mut large): (Vec<u32>, Vec<u32>) =
+            (0..n as u32).partition(|&i| prob[i as usize] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            alias[s as usize] = l;
+            prob[l as usize] -= 1.0 - prob[s as usize];
+            if prob[l as usize] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // leftovers are only off from 1 by rounding
+        for i in large.into_iter().chain(small) {
+            if prob[i as usize] > 0.0 {
+                prob[i as usize] = 1.0;
+            }
+        }
+        (prob, alias)
+    }
+
+    /// No-op if `i` is dead.
+    pub fn remove(&mut self, i: u32) {
+        if !self.alive[i as usize] {
+            return;
+        }
+        self.alive[i as usize] = false;
+        self.alive_weight -= self.weights[i as usize];
+        if self.alive_weight < self.table_weight / 2.0 {
+            let (prob, alias) = Self::alias_table(&self.weights, &self.alive);
+            self.prob = prob;
+            self.alias = alias;
+            self.alive_weight = (0..self.weights.len())
+                .filter(|&j| self.alive[j])
+                .map(|j| self.weights[j])
+                .sum();
+            self.table_weight = self.alive_weight;
+        }
+    }
+
+    /// Reverts to original domain.
+    pub fn reset(&mut self) {
+        self.alive.iter_mut().for_each(|a| *a = true);
+        self.alive_weight = self.weights.iter().sum();
+        self.table_weight = self.alive_weight;
+        self.prob.clone_from(&self.full_prob);
+        self.alias.clone_from(&self.full_alias);
+    }
+
+    /// Samples from alive domain, which must have positive weight.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> u32 {
+        assert!(self.alive_weight > 0.0, "no weight left to sample");
+        loop {
+            let i = rng.gen_range(0..self.prob.len());
+            let j = if rng.gen::<f64>() < self.prob[i] {
+                i as u32
+            } else {
+                self.alias[i]
+            };
+            if self.alive[j as usize] {
+                return j;
+            }
+        }
+    }
+}
+
 /// See examples/color.rs for an explanation of the logging format.
+///
+/// The elapsed time excludes time spent between the start and end of each
+/// `log` call, so it only accounts for sampling.
 struct GlauberLogger {
-    steps: u64,
     elapsed_seconds: f64,
-    start_time: Option<Instant>,
+    last_log: Option<Instant>,
     time_file: BufWriter<File>,
     color_file: BufWriter<File>,
-    steps_history: Vec<u64>,
-    times_history: Vec<f64>,
 }
 
 impl GlauberLogger {
@@ -309,51 +2112,491 @@ impl GlauberLogger {
         let file = File::create(out_times).expect("write file");
         let time_file = BufWriter::new(file);
         Self {
-            steps: 0,
             elapsed_seconds: 0.0,
-            start_time: None,
+            last_log: None,
             time_file,
             color_file,
-            steps_history: Vec::new(),
-            times_history: Vec::new(),
         }
     }
 
-    fn start(&mut self) {
-        assert!(self.start_time.is_none());
-        self.start_time = Some(Instant::now());
-    }
-
-    fn stop(&mut self, samples: u64) {
-        let start_time = self.start_time.expect("started clock");
-        let delta_seconds = Instant::now().duration_since(start_time);
-        self.elapsed_seconds += delta_seconds.as_secs_f64();
-        self.steps += samples;
-        self.start_time = None;
-    }
-
-    fn log(&mut self, colors: &mut [Rwu32]) {
-        write!(self.color_file, "{}", self.steps).expect("steps write");
-        self.steps_history.push(self.steps);
+    fn log(&mut self, steps: usize, colors: &[u32]) {
+        if let Some(last_log) = self.last_log {
+            self.elapsed_seconds += Instant::now().duration_since(last_log).as_secs_f64();
+        }
+        write!(self.color_file, "{}", steps).expect("steps write");
         for c in colors {
-            let c = c.mut_read();
             write!(self.color_file, " {}", c).expect("add color");
         }
-        writeln!(self.color_file, "").expect("write newline");
+        writeln!(self.color_file).expect("write newline");
         writeln!(self.time_file, "{}", self.elapsed_seconds).expect("write seconds");
-        self.times_history.push(self.elapsed_seconds);
+        self.last_log = Some(Instant::now());
     }
 }
 
 struct SamplerThreadState {
-    rng: Lcg64Xsh32,
+    rng: Box<dyn GlauberRng>,
     viable_colors: DiscreteSampler,
+    timestamps: Vec<Duration>,
 }
 
 impl SamplerThreadState {
-    fn new(idx: usize, ncolors: u32) -> Self {
-        let rng = Lcg64Xsh32::new(0xcafef00dd15ea5e5, idx.try_into().unwrap());
+    fn new(stream: u64, ncolors: u32) -> Self {
+        Self::with_rng(default_rng_factory(stream), ncolors)
+    }
+
+    fn with_rng(rng: Box<dyn GlauberRng>, ncolors: u32) -> Self {
         let viable_colors = DiscreteSampler::new(ncolors);
-        Self { rng, viable_colors }
+        Self {
+            rng,
+            viable_colors,
+            timestamps: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::is_bipartite;
+
+    fn wheel(rim: u32) -> Graph {
+        let edges: Vec<_> = (1..=rim)
+            .flat_map(|v| vec![(0, v), (v, v % rim + 1)])
+            .collect();
+        Graph::from_edge_pairs(rim as usize + 1, &edges)
+    }
+
+    #[test]
+    fn glauber_timed_stops_near_deadline() {
+        let graph = crate::generators::watts_strogatz(500, 6, 0.3, 1).unwrap();
+        let ncolors = 2 * graph.max_degree() as u32 + 1;
+        let duration = Duration::from_millis(200);
+        let start = Instant::now();
+        let colors = glauber_timed(&graph, ncolors, duration, 100, 3);
+        assert!(Instant::now().duration_since(start) < 2 * duration);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+    }
+
+    #[test]
+    fn glauber_mh_with_constant_weight_stays_proper() {
+        let graph = crate::generators::watts_strogatz(200, 6, 0.3, 2).unwrap();
+        let ncolors = 2 * graph.max_degree() as u32 + 1;
+        let colors = glauber_mh(&graph, ncolors, 20_000, 4, |_| 0.0);
+        assert_eq!(colors.len(), graph.nvertices());
+        assert!(colors.iter().all(|&c| c < ncolors));
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+    }
+
+    #[test]
+    fn remap_inverse_roundtrip() {
+        let colors = vec![2, 0, 2, 1, 0, 2, 3, 0];
+        let remapped = remap(4, &colors);
+        assert_eq!(remapped, vec![1, 1, 2, 1, 2, 3, 1, 3]);
+        let inverse = remap_inverse(4, &colors, &remapped);
+        for (v, (&c, &r)) in colors.iter().zip(&remapped).enumerate() {
+            assert_eq!(inverse[c as usize][r as usize - 1], v as u32);
+        }
+    }
+
+    #[test]
+    fn improper_colorings_report_conflicts() {
+        let c5 = Graph::from_edge_pairs(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        assert_eq!(
+            verify_proper_coloring(&c5, &[0, 1, 0, 1, 0]),
+            Err(vec![(0, 4)])
+        );
+        assert_eq!(
+            verify_proper_coloring(&c5, &[0, 0, 0, 1, 1]),
+            Err(vec![(0, 1), (1, 2), (3, 4)])
+        );
+        assert_eq!(verify_proper_coloring(&c5, &[0, 1, 0, 1, 2]), Ok(()));
+    }
+
+    #[test]
+    fn balanced_classes_stay_proper() {
+        let graph = crate::generators::watts_strogatz(300, 6, 0.2, 5).unwrap();
+        let (greedy_ncolors, mut colors) = greedy(&graph, VertexOrdering::DegreeLargestFirst);
+        let ncolors = greedy_ncolors + 2;
+        let spread = |sizes: &[usize]| sizes.iter().max().unwrap() - sizes.iter().min().unwrap();
+        let mut before = vec![0; ncolors as usize];
+        colors.iter().for_each(|&c| before[c as usize] += 1);
+        let after = balance_classes(&graph, &mut colors, ncolors);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+        assert_eq!(after.iter().sum::<usize>(), graph.nvertices());
+        assert!(spread(&after) < spread(&before));
+    }
+
+    #[test]
+    fn autocorrelation_of_independent_and_repeated() {
+        let mut rng = seeded_rng(1);
+        let independent: Vec<Vec<u32>> = (0..5000)
+            .map(|_| (0..50).map(|_| rng.gen_range(0..3)).collect())
+            .collect();
+        let rho = autocorrelation(&independent, 5);
+        assert!((rho[0] - 1.0).abs() < 1e-9);
+        for r in &rho[1..] {
+            assert!(r.abs() < 0.05, "{:?}", rho);
+        }
+        let repeated = vec![independent[0].clone(); 20];
+        assert_eq!(autocorrelation(&repeated, 5), vec![1.0; 6]);
+    }
+
+    #[test]
+    fn gelman_rubin_of_identical_and_opposite_chains() {
+        // proper 3-colorings of C_6, with three or two vertices colored 0
+        let high = vec![vec![0, 1, 0, 1, 0, 1], vec![0, 1, 0, 1, 2, 1]];
+        // and with none or one
+        let low = vec![vec![1, 2, 1, 2, 1, 2], vec![0, 2, 1, 2, 1, 2]];
+        let chain = |snapshots: &[Vec<u32>]| -> Vec<Vec<u32>> {
+            (0..20).map(|t| snapshots[t % 2].clone()).collect()
+        };
+        let constant = vec![high[0].clone(); 20];
+        assert_eq!(gelman_rubin(&[constant.clone(), constant]), 1.0);
+        let identical = gelman_rubin(&[chain(&high), chain(&high), chain(&high)]);
+        assert!((identical - 1.0).abs() < 0.05, "{}", identical);
+        let opposite = gelman_rubin(&[chain(&high), chain(&low)]);
+        assert!(opposite > 1.5, "{}", opposite);
+    }
+
+    #[test]
+    fn multi_chain_keeps_the_best_chain() {
+        // chains on a short cycle end up using anywhere from two to five of
+        // its colors
+        let edges: Vec<(Vertex, Vertex)> = (0..6).map(|v| (v, (v + 1) % 6)).collect();
+        let graph = Graph::from_edge_pairs(6, &edges);
+        let result = glauber_multi_chain(&graph, 5, 20_000, 8, 1);
+        assert!(verify_proper_coloring(&graph, &result.colors).is_ok());
+        let best = result.best_chain;
+        let key = |c: usize| (result.ncolors_used[c], result.conflicts[c]);
+        assert!((0..8).all(|c| key(c) >= key(best)));
+        assert!((0..best).all(|c| key(c) > key(best)));
+        assert_eq!(actual_ncolors(&result.colors), result.ncolors_used[best]);
+        // the chains mix quickly, so they agree
+        assert_eq!(result.rhat.len(), graph.nvertices());
+        let max_rhat = result
+            .rhat
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!((1.0..1.1).contains(&max_rhat), "{}", max_rhat);
+        assert!(result
+            .unchanged_fraction
+            .iter()
+            .all(|f| (0.0..=1.0).contains(f)));
+
+        // two chains share four threads
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let shared = pool.install(|| glauber_multi_chain(&graph, 5, 20_000, 2, 1));
+        assert!(verify_proper_coloring(&graph, &shared.colors).is_ok());
+        assert_eq!(shared.rhat.len(), 6);
+    }
+
+    #[test]
+    fn snapshots_bin_roundtrip() {
+        let dir = crate::test_dir("snapshots-bin");
+        // packed colors only beat ASCII when most of them take at least four
+        // digits, as in this coloring of K_1500
+        let graph = Graph::complete(1500);
+        let (_, colors) = greedy(&graph, VertexOrdering::Natural);
+        let snapshots: Vec<(usize, Vec<u32>)> = (0..5)
+            .map(|i| {
+                let mut colors = colors.clone();
+                colors.rotate_left(i);
+                (i * 1000, colors)
+            })
+            .collect();
+        let path = dir.join("snapshots.bin");
+        write_snapshots_bin(&snapshots, &path).unwrap();
+        assert_eq!(read_snapshots_bin(&path).unwrap(), snapshots);
+
+        let ascii = dir.join("snapshots.txt");
+        let mut logger = GlauberLogger::new(&ascii, &dir.join("snapshots.times"));
+        for (steps, colors) in &snapshots {
+            logger.log(*steps, colors);
+        }
+        drop(logger);
+        let size = |p: &Path| std::fs::metadata(p).unwrap().len();
+        assert!(size(&path) < size(&ascii));
+
+        write_snapshots_bin(&[], &path).unwrap();
+        assert!(read_snapshots_bin(&path).unwrap().is_empty());
+        std::fs::write(&path, [1, 0]).unwrap();
+        assert!(read_snapshots_bin(&path).is_err());
+    }
+
+    #[test]
+    fn glauber_same_seed_is_reproducible() {
+        let graph = crate::generators::watts_strogatz(200, 6, 0.3, 1).unwrap();
+        let ncolors = 2 * graph.max_degree() as u32 + 1;
+        let dir = crate::test_dir("glauber-seed");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let run = |name: &str, seed: u64| {
+            let out = dir.join(format!("{}.colors", name));
+            let out_times = dir.join(format!("{}.times", name));
+            let (colors, actual) =
+                pool.install(|| glauber(&graph, ncolors, 5000, 1000, &out, &out_times, seed));
+            assert!(actual <= ncolors);
+            assert_eq!(actual, actual_ncolors(&colors));
+            assert!(verify_proper_coloring(&graph, &colors).is_ok());
+            (colors, std::fs::read(out).unwrap())
+        };
+        let (colors, log) = run("first", 42);
+        assert_eq!(run("second", 42), (colors.clone(), log));
+        assert_ne!(run("other", 43).0, colors);
+    }
+
+    #[test]
+    fn greedy_orderings() {
+        let path = Graph::from_edge_pairs(4, &[(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(
+            greedy(&path, VertexOrdering::Natural),
+            (2, vec![0, 1, 0, 1])
+        );
+        let c5 = Graph::from_edge_pairs(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        assert_eq!(
+            greedy(&c5, VertexOrdering::Natural),
+            (3, vec![0, 1, 0, 1, 2])
+        );
+
+        // the crown graph, K_{k,k} without a perfect matching, where u_i = 2i
+        // and v_i = 2i + 1 are adjacent iff i != j, takes k colors in natural
+        // order but two with either side first
+        let k = 6;
+        let edges: Vec<_> = (0..k)
+            .flat_map(|i| {
+                (0..k)
+                    .filter(move |&j| j != i)
+                    .map(move |j| (2 * i, 2 * j + 1))
+            })
+            .collect();
+        let crown = Graph::from_edge_pairs(2 * k as usize, &edges);
+        assert_eq!(greedy(&crown, VertexOrdering::Natural).0, k);
+        let sides = (0..k).map(|i| 2 * i).chain((0..k).map(|i| 2 * i + 1));
+        let (ncolors, colors) = greedy(&crown, VertexOrdering::Custom(sides.collect()));
+        assert_eq!(ncolors, 2);
+        assert!(verify_proper_coloring(&crown, &colors).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "custom ordering repeats or is missing a vertex")]
+    fn custom_ordering_must_be_a_permutation() {
+        let path = Graph::from_edge_pairs(3, &[(0, 1), (1, 2)]);
+        greedy(&path, VertexOrdering::Custom(vec![0, 1, 1]));
+    }
+
+    #[test]
+    fn color_pairs_are_bipartite() {
+        let graph = crate::generators::watts_strogatz(300, 8, 0.2, 4).unwrap();
+        let (ncolors, colors) = greedy(&graph, VertexOrdering::DegreeLargestFirst);
+        for c1 in 0..ncolors {
+            for c2 in c1 + 1..ncolors {
+                let pair = color_class_subgraph(&graph, &colors, c1, c2);
+                let size = colors.iter().filter(|&&c| c == c1 || c == c2).count();
+                assert_eq!(pair.nvertices(), size);
+                assert!(is_bipartite(&pair).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn discrete_sampler_insert() {
+        let mut sampler = DiscreteSampler::new(4);
+        let mut rng = seeded_rng(2);
+        sampler.remove(1);
+        sampler.remove(3);
+        assert_eq!(sampler.nalive(), 2);
+        assert!((0..1000).all(|_| sampler.sample(&mut rng).is_multiple_of(2)));
+        sampler.insert(3);
+        sampler.insert(3);
+        assert_eq!(sampler.nalive(), 3);
+        assert!((0..1000).any(|_| sampler.sample(&mut rng) == 3));
+        assert!((0..1000).all(|_| sampler.sample(&mut rng) != 1));
+        sampler.remove(0);
+        sampler.insert(1);
+        let mut seen = [false; 4];
+        (0..1000).for_each(|_| seen[sampler.sample(&mut rng) as usize] = true);
+        assert_eq!(seen, [false, true, true, true]);
+        sampler.reset();
+        assert_eq!(sampler.nalive(), 4);
+    }
+
+    #[test]
+    fn weighted_sampler_frequencies() {
+        let weights = vec![1.0, 0.0, 2.0, 3.0, 4.0];
+        let mut sampler = WeightedDiscreteSampler::new(weights.clone());
+        let mut rng = seeded_rng(0);
+        let nsamples = 200_000;
+        let frequencies = |sampler: &WeightedDiscreteSampler, rng: &mut _| {
+            let mut counts = vec![0usize; weights.len()];
+            for _ in 0..nsamples {
+                counts[sampler.sample(rng) as usize] += 1;
+            }
+            counts
+                .into_iter()
+                .map(|c| c as f64 / nsamples as f64)
+                .collect::<Vec<_>>()
+        };
+
+        let freqs = frequencies(&sampler, &mut rng);
+        for (f, w) in freqs.iter().zip(&weights) {
+            assert!((f - w / 10.0).abs() < 0.01, "{:?}", freqs);
+        }
+
+        // dropping most of the mass rebuilds the tables
+        sampler.remove(4);
+        sampler.remove(3);
+        let freqs = frequencies(&sampler, &mut rng);
+        let expected = [1.0 / 3.0, 0.0, 2.0 / 3.0, 0.0, 0.0];
+        for (f, e) in freqs.iter().zip(&expected) {
+            assert!((f - e).abs() < 0.01, "{:?}", freqs);
+        }
+
+        sampler.reset();
+        let freqs = frequencies(&sampler, &mut rng);
+        for (f, w) in freqs.iter().zip(&weights) {
+            assert!((f - w / 10.0).abs() < 0.01, "{:?}", freqs);
+        }
+    }
+
+    #[test]
+    fn k_colorability_of_odd_cycle_and_clique() {
+        let limit = Duration::from_secs(1);
+        let c5 = Graph::from_edge_pairs(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        assert!(is_k_colorable(&c5, 2, limit).is_none());
+        let colors = is_k_colorable(&c5, 3, limit).unwrap();
+        assert!(colors.iter().all(|&c| c < 3));
+        assert!(verify_proper_coloring(&c5, &colors).is_ok());
+        let k4 = Graph::complete(4);
+        assert!(is_k_colorable(&k4, 3, limit).is_none());
+        assert!(is_k_colorable(&k4, 4, limit).is_some());
+    }
+
+    #[test]
+    fn smallest_last_colors_wheels_optimally() {
+        // wheels need 4 colors around an odd rim and 3 around an even one
+        for rim in [5, 7, 9, 15] {
+            let (ncolors, colors) = greedy_smallest_last(&wheel(rim));
+            assert_eq!(ncolors, 4);
+            assert!(verify_proper_coloring(&wheel(rim), &colors).is_ok());
+            assert_eq!(greedy(&wheel(rim), VertexOrdering::SmallestLast).0, 4);
+        }
+        for rim in [4, 6, 10] {
+            assert_eq!(greedy_smallest_last(&wheel(rim)).0, 3);
+        }
+    }
+
+    #[test]
+    fn local_glauber_only_recolors_the_region() {
+        let graph = crate::generators::grid_2d(10, 10);
+        let ncolors = 2 * graph.max_degree() as u32 + 1;
+        let (_, mut colors) = greedy(&graph, VertexOrdering::Natural);
+        let before = colors.clone();
+        // the 5 x 5 corner of the grid, which is recolored along with its
+        // neighbors
+        let region: Vec<Vertex> = (0..5)
+            .flat_map(|r| (0..5).map(move |c| 10 * r + c))
+            .collect();
+        local_glauber(&graph, &mut colors, ncolors, &region, 10_000, 7);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+        assert!(colors.iter().all(|&c| c < ncolors));
+        let touched = |v: Vertex| {
+            region.contains(&v) || graph.neighbors(v).iter().any(|w| region.contains(w))
+        };
+        for v in 0..graph.nvertices() as Vertex {
+            if !touched(v) {
+                assert_eq!(colors[v as usize], before[v as usize], "vertex {}", v);
+            }
+        }
+        assert_ne!(colors, before);
+    }
+
+    #[test]
+    fn fractional_chromatic_cycle() {
+        let c5 = Graph::from_edge_pairs(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        // the bound is certified, so it never exceeds the true value 5 / 2
+        let value = fractional_chromatic_lb(&c5);
+        assert!((2.4..=2.5 + 1e-9).contains(&value), "{}", value);
+        let k4 = Graph::complete(4);
+        assert!((fractional_chromatic_lb(&k4) - 4.0).abs() < 1e-6);
+
+        let ones = [1.0; 5];
+        match heaviest_independent_set(&c5, &ones, usize::MAX) {
+            HeavySetSearch::Found(weight, set) => {
+                assert_eq!(weight, 2.0);
+                assert!(set.iter().all(|&u| set.iter().all(|&v| !c5.has_edge(u, v))));
+            }
+            _ => panic!("C5 has independent sets of size 2"),
+        }
+        assert!(matches!(
+            heaviest_independent_set(&c5, &[0.4; 5], usize::MAX),
+            HeavySetSearch::NoneHeavy
+        ));
+        assert!(matches!(
+            heaviest_independent_set(&c5, &ones, 0),
+            HeavySetSearch::GaveUp
+        ));
+        // covered by two edges and a vertex
+        assert_eq!(clique_cover_bound(&c5, &ones), 3.0);
+        assert_eq!(clique_cover_bound(&k4, &[0.5, 0.25, 0.25, 0.1]), 0.5);
+    }
+
+    #[test]
+    fn glauber_warm_starts_from_the_given_coloring() {
+        let graph = crate::generators::watts_strogatz(200, 6, 0.3, 3).unwrap();
+        let ncolors = 2 * graph.max_degree() as u32 + 1;
+        let (_, initial) = greedy(&graph, VertexOrdering::Random(1));
+        assert_eq!(
+            glauber_warm(&graph, ncolors, 0, initial.clone(), 5),
+            initial
+        );
+        // every step recolors at most one vertex
+        let colors = glauber_warm(&graph, ncolors, 100, initial.clone(), 5);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+        let changed = colors.iter().zip(&initial).filter(|(a, b)| a != b).count();
+        assert!(0 < changed && changed <= 100, "{}", changed);
+    }
+
+    #[test]
+    fn conflict_matrix_of_proper_coloring() {
+        let graph = crate::generators::watts_strogatz(300, 8, 0.2, 4).unwrap();
+        let (ncolors, colors) = greedy(&graph, VertexOrdering::DegreeLargestFirst);
+        let matrix = conflict_matrix(&graph, &colors, ncolors);
+        let mut total = 0;
+        for (c1, row) in matrix.iter().enumerate() {
+            assert_eq!(row[c1], 0);
+            for (c2, &count) in row.iter().enumerate() {
+                assert_eq!(count, matrix[c2][c1]);
+                total += count;
+            }
+        }
+        assert_eq!(total as usize, 2 * graph.nedges());
+        let matrix = conflict_matrix(&graph, &vec![0; graph.nvertices()], 1);
+        assert_eq!(matrix, vec![vec![graph.nedges() as u32]]);
+    }
+
+    #[test]
+    fn compress_palette_stays_proper() {
+        // every vertex starts with its own color
+        let graph = crate::generators::grid_2d(8, 9);
+        let n = graph.nvertices() as u32;
+        let mut colors: Vec<u32> = (0..n).collect();
+        let ncolors = compress_palette(&graph, &mut colors, n);
+        assert!(ncolors <= n);
+        assert!(ncolors < 10, "{}", ncolors);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
+        assert!(colors.iter().all(|&c| c < ncolors));
+        assert_eq!(actual_ncolors(&colors), ncolors);
+
+        let graph = crate::generators::watts_strogatz(300, 6, 0.2, 5).unwrap();
+        let (greedy_ncolors, mut colors) = greedy(&graph, VertexOrdering::Random(6));
+        let ncolors = compress_palette(&graph, &mut colors, greedy_ncolors);
+        assert!(ncolors <= greedy_ncolors);
+        assert!(verify_proper_coloring(&graph, &colors).is_ok());
     }
 }
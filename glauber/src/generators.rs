@@ -0,0 +1,412 @@
+//! Random and structured graph generators.
+
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::graph::{Graph, Vertex};
+use crate::seeded_rng;
+
+/// Reasons a generator can fail to produce a graph.
+#[derive(Debug)]
+pub enum GeneratorError {
+    /// The requested parameters don't describe any simple graph.
+    InvalidParameters(String),
+    /// No graph exists since the total degree `n * d` is odd.
+    OddDegreeSum { n: u32, d: u32 },
+    /// The generator gave up after exhausting its retry budget.
+    TooManyRetries(usize),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameters(msg) => write!(f, "invalid generator parameters: {}", msg),
+            Self::OddDegreeSum { n, d } => write!(f, "n {} * d {} is odd", n, d),
+            Self::TooManyRetries(retries) => write!(f, "gave up after {} retries", retries),
+        }
+    }
+}
+
+impl Error for GeneratorError {}
+
+/// Samples a Watts-Strogatz small-world graph over `n` vertices.
+///
+/// Starts from a ring lattice where each vertex is adjacent to its `k / 2`
+/// nearest neighbors on either side, so `k` must be even, then rewires each
+/// lattice edge `(u, u + j)` with probability `beta` to `(u, w)` for a
+/// uniformly random `w` which is neither `u` nor already adjacent to `u`.
+pub fn watts_strogatz(n: u32, k: u32, beta: f64, seed: u64) -> Result<Graph, GeneratorError> {
+    if k >= n {
+        return Err(GeneratorError::InvalidParameters(format!(
+            "k {} >= n {}",
+            k, n
+        )));
+    }
+    if !k.is_multiple_of(2) {
+        return Err(GeneratorError::InvalidParameters(format!("k {} is odd", k)));
+    }
+    if !(0.0..=1.0).contains(&beta) {
+        return Err(GeneratorError::InvalidParameters(format!(
+            "beta {} not in [0, 1]",
+            beta
+        )));
+    }
+
+    let mut rng = seeded_rng(seed);
+    let mut adjacency: Vec<BTreeSet<Vertex>> = vec![BTreeSet::new(); n as usize];
+    for u in 0..n {
+        for j in 1..=(k / 2) {
+            let v = (u + j) % n;
+            adjacency[u as usize].insert(v);
+            adjacency[v as usize].insert(u);
+        }
+    }
+
+    for j in 1..=(k / 2) {
+        for u in 0..n {
+            let v = (u + j) % n;
+            if !rng.gen_bool(beta) || adjacency[u as usize].len() + 1 >= n as usize {
+                continue;
+            }
+            let w = loop {
+                let w = rng.gen_range(0..n);
+                if w != u && !adjacency[u as usize].contains(&w) {
+                    break w;
+                }
+            };
+            adjacency[u as usize].remove(&v);
+            adjacency[v as usize].remove(&u);
+            adjacency[u as usize].insert(w);
+            adjacency[w as usize].insert(u);
+        }
+    }
+
+    Ok(Graph::from_lists(
+        adjacency
+            .into_iter()
+            .map(|s| s.into_iter().collect())
+            .collect(),
+    ))
+}
+
+/// Samples a random `d`-regular simple graph over `n` vertices with the
+/// configuration model.
+///
+/// The `n * d` half-edges are shuffled and paired up, after which any
+/// self-loops or repeated edges are removed by swapping endpoints with other
+/// randomly chosen pairs, rather than regenerating the whole pairing.
+pub fn random_regular(n: u32, d: u32, seed: u64) -> Result<Graph, GeneratorError> {
+    if u64::from(n) * u64::from(d) % 2 == 1 {
+        return Err(GeneratorError::OddDegreeSum { n, d });
+    }
+    if d >= n.max(1) {
+        return Err(GeneratorError::InvalidParameters(format!(
+            "d {} >= n {}",
+            d, n
+        )));
+    }
+
+    let mut rng = seeded_rng(seed);
+    let mut stubs: Vec<Vertex> = (0..n)
+        .flat_map(|v| std::iter::repeat_n(v, d as usize))
+        .collect();
+    stubs.shuffle(&mut rng);
+    let mut pairs: Vec<(Vertex, Vertex)> = stubs.chunks(2).map(|s| (s[0], s[1])).collect();
+
+    let key = |(u, v): (Vertex, Vertex)| (u.min(v), u.max(v));
+    let mut counts: HashMap<(Vertex, Vertex), u32> = HashMap::new();
+    for &p in &pairs {
+        *counts.entry(key(p)).or_default() += 1;
+    }
+    let is_bad = |counts: &HashMap<_, u32>, (u, v)| u == v || counts[&key((u, v))] > 1;
+
+    let mut bad: Vec<usize> = (0..pairs.len())
+        .filter(|&i| is_bad(&counts, pairs[i]))
+        .collect();
+    let max_retries = 100 * pairs.len().max(1);
+    let mut retries = 0;
+    while !bad.is_empty() {
+        // fixing bad pairs in a fixed order can get stuck, so pick at random
+        let k = rng.gen_range(0..bad.len());
+        let i = bad[k];
+        if !is_bad(&counts, pairs[i]) {
+            bad.swap_remove(k);
+            continue;
+        }
+
+        retries += 1;
+        if retries > max_retries {
+            return Err(GeneratorError::TooManyRetries(max_retries));
+        }
+
+        let j = rng.gen_range(0..pairs.len());
+        if i == j {
+            continue;
+        }
+        let old = [pairs[i], pairs[j]];
+        let (a, b) = pairs[i];
+        let (c, e) = if rng.gen() {
+            pairs[j]
+        } else {
+            (pairs[j].1, pairs[j].0)
+        };
+        let new = [(a, c), (b, e)];
+
+        // accept swaps which don't increase the number of self-loops and
+        // surplus edge copies among the affected keys, so that the search
+        // can move across plateaus rather than getting stuck
+        let mut keys: Vec<_> = old.iter().chain(new.iter()).map(|&p| key(p)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let defects = |counts: &HashMap<_, u32>| -> u32 {
+            keys.iter()
+                .map(|&(u, v)| {
+                    let count = counts.get(&(u, v)).copied().unwrap_or(0);
+                    if u == v {
+                        count
+                    } else {
+                        count.saturating_sub(1)
+                    }
+                })
+                .sum()
+        };
+        let before = defects(&counts);
+        for &p in &old {
+            *counts.get_mut(&key(p)).unwrap() -= 1;
+        }
+        for &p in &new {
+            *counts.entry(key(p)).or_default() += 1;
+        }
+        if defects(&counts) <= before {
+            pairs[i] = new[0];
+            pairs[j] = new[1];
+            bad.push(j);
+        } else {
+            for &p in &new {
+                *counts.get_mut(&key(p)).unwrap() -= 1;
+            }
+            for &p in &old {
+                *counts.get_mut(&key(p)).unwrap() += 1;
+            }
+        }
+    }
+
+    let mut lists: Vec<Vec<Vertex>> = vec![Vec::with_capacity(d as usize); n as usize];
+    for (u, v) in pairs {
+        lists[u as usize].push(v);
+        lists[v as usize].push(u);
+    }
+    lists.iter_mut().for_each(|l| l.sort_unstable());
+    Ok(Graph::from_lists(lists))
+}
+
+/// Returns the `rows` by `cols` grid lattice, where vertex `i * cols + j` is
+/// adjacent to its (up to) four axis-aligned neighbors, without wraparound.
+pub fn grid_2d(rows: u32, cols: u32) -> Graph {
+    lattice_2d(rows, cols, false)
+}
+
+/// Returns the `rows` by `cols` toroidal lattice, which is the same as
+/// [`grid_2d`] but with rows and columns wrapping around.
+pub fn torus_2d(rows: u32, cols: u32) -> Graph {
+    lattice_2d(rows, cols, true)
+}
+
+fn lattice_2d(rows: u32, cols: u32, wrap: bool) -> Graph {
+    let n = rows.checked_mul(cols).expect("lattice size overflow");
+    let lists = (0..n)
+        .map(|v| {
+            let (i, j) = (v / cols, v % cols);
+            let mut neighbors = Vec::with_capacity(4);
+            if i > 0 {
+                neighbors.push(v - cols);
+            } else if wrap {
+                neighbors.push((rows - 1) * cols + j);
+            }
+            if i + 1 < rows {
+                neighbors.push(v + cols);
+            } else if wrap {
+                neighbors.push(j);
+            }
+            if j > 0 {
+                neighbors.push(v - 1);
+            } else if wrap {
+                neighbors.push(i * cols + cols - 1);
+            }
+            if j + 1 < cols {
+                neighbors.push(v + 1);
+            } else if wrap {
+                neighbors.push(i * cols);
+            }
+            // small tori wrap onto existing neighbors or the vertex itself
+            neighbors.retain(|&w| w != v);
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            neighbors
+        })
+        .collect();
+    Graph::from_lists(lists)
+}
+
+/// Samples a stochastic block model graph, where the vertices are split into
+/// contiguous blocks of the given sizes, with each pair of vertices within the
+/// same block adjacent with probability `p_in` and each pair across blocks
+/// adjacent with probability `p_out`, independently.
+///
+/// Rather than flipping a coin for every pair, this draws geometrically
+/// distributed skips between included pairs, so the running time is
+/// proportional to the number of edges plus the number of block pairs.
+pub fn stochastic_block_model(block_sizes: &[u32], p_in: f64, p_out: f64, seed: u64) -> Graph {
+    assert!((0.0..=1.0).contains(&p_in), "p_in {} not in [0, 1]", p_in);
+    assert!(
+        (0.0..=1.0).contains(&p_out),
+        "p_out {} not in [0, 1]",
+        p_out
+    );
+
+    let mut starts = Vec::with_capacity(block_sizes.len() + 1);
+    starts.push(0u32);
+    for &size in block_sizes {
+        let last = *starts.last().unwrap();
+        starts.push(last.checked_add(size).expect("too many vertices"));
+    }
+    let n = *starts.last().unwrap();
+
+    let mut rng = seeded_rng(seed);
+    let mut lists: Vec<Vec<Vertex>> = vec![Vec::new(); n as usize];
+    for (a, &size_a) in block_sizes.iter().enumerate() {
+        let base_a = starts[a];
+        let size_a = u64::from(size_a);
+        sample_indices(&mut rng, size_a * size_a.saturating_sub(1) / 2, p_in, |x| {
+            let (i, j) = triangle_pair(x);
+            let (u, v) = (base_a + i, base_a + j);
+            lists[u as usize].push(v);
+            lists[v as usize].push(u);
+        });
+        for (b, &size_b) in block_sizes.iter().enumerate().skip(a + 1) {
+            let base_b = starts[b];
+            let size_b = u64::from(size_b);
+            sample_indices(&mut rng, size_a * size_b, p_out, |x| {
+                let u = base_a + (x / size_b) as u32;
+                let v = base_b + (x % size_b) as u32;
+                lists[u as usize].push(v);
+                lists[v as usize].push(u);
+            });
+        }
+    }
+    lists.iter_mut().for_each(|l| l.sort_unstable());
+    Graph::from_lists(lists)
+}
+
+/// Calls `emit` on each index in `0..n`, in increasing order, independently
+/// with probability `p`, by skipping ahead geometrically distributed amounts.
+///
+/// See Batagelj and Brandes, "Efficient generation of large random networks".
+fn sample_indices<R: Rng>(rng: &mut R, n: u64, p: f64, mut emit: impl FnMut(u64)) {
+    if p <= 0.0 {
+        return;
+    }
+    if p >= 1.0 {
+        (0..n).for_each(emit);
+        return;
+    }
+    let log_q = (1.0 - p).ln();
+    let mut x = 0u64;
+    while x < n {
+        let r: f64 = rng.gen();
+        let skip = ((1.0 - r).ln() / log_q).floor();
+        if skip >= (n - x) as f64 {
+            break;
+        }
+        x += skip as u64;
+        emit(x);
+        x += 1;
+    }
+}
+
+/// Inverts the enumeration of pairs `(i, j)` with `j < i` given by
+/// `x = i * (i - 1) / 2 + j`.
+fn triangle_pair(x: u64) -> (u32, u32) {
+    let c2 = |i: u64| i * i.saturating_sub(1) / 2;
+    let mut i = ((1.0 + (1.0 + 8.0 * x as f64).sqrt()) / 2.0) as u64;
+    while c2(i) > x {
+        i -= 1;
+    }
+    while c2(i + 1) <= x {
+        i += 1;
+    }
+    ((i as u32), (x - c2(i)) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::average_clustering_coefficient;
+
+    fn is_simple(g: &Graph) -> bool {
+        (0..g.nvertices() as Vertex).all(|v| {
+            let neighbors = g.neighbors(v);
+            !neighbors.contains(&v) && neighbors.windows(2).all(|w| w[0] < w[1])
+        })
+    }
+
+    #[test]
+    fn watts_strogatz_interpolates() {
+        let (n, k) = (1000, 6);
+        let lattice = watts_strogatz(n, k, 0.0, 1).unwrap();
+        assert!((0..n).all(|v| lattice.degree(v) == k as usize));
+        assert!(lattice.neighbors(0).contains(&3) && lattice.neighbors(0).contains(&(n - 3)));
+        assert!(!lattice.neighbors(0).contains(&4));
+        // a ring lattice has clustering 3 (k - 2) / 4 (k - 1)
+        let lattice_clustering = average_clustering_coefficient(&lattice);
+        assert!((lattice_clustering - 0.6).abs() < 1e-9);
+        // a random graph of the same density has clustering about k / n
+        let random = watts_strogatz(n, k, 1.0, 1).unwrap();
+        assert_eq!(random.nedges(), lattice.nedges());
+        assert!(average_clustering_coefficient(&random) < 0.05);
+        assert!(watts_strogatz(n, 5, 0.5, 1).is_err());
+        assert!(watts_strogatz(6, 6, 0.5, 1).is_err());
+    }
+
+    #[test]
+    fn random_regular_degrees() {
+        for (n, d) in [(100, 3), (51, 4), (200, 10)] {
+            let g = random_regular(n, d, 2).unwrap();
+            assert_eq!(g.nvertices(), n as usize);
+            assert!((0..n).all(|v| g.degree(v) == d as usize));
+            assert!(is_simple(&g));
+        }
+        assert!(random_regular(5, 3, 2).is_err());
+    }
+
+    #[test]
+    fn grid_and_torus_degrees() {
+        let grid = grid_2d(4, 5);
+        assert_eq!(grid.nvertices(), 20);
+        assert_eq!(grid.nedges(), 4 * 4 + 3 * 5);
+        assert_eq!(grid.degree(0), 2);
+        assert_eq!(grid.degree(6), 4);
+        let torus = torus_2d(4, 5);
+        assert_eq!(torus.nedges(), 2 * 20);
+        assert!((0..20).all(|v| torus.degree(v) == 4));
+    }
+
+    #[test]
+    fn sbm_edge_counts() {
+        let (size, p_in, p_out) = (200u32, 0.1, 0.02);
+        let g = stochastic_block_model(&[size, size], p_in, p_out, 3);
+        assert!(is_simple(&g));
+        let across: usize = (0..size)
+            .map(|u| g.neighbors(u).iter().filter(|&&v| v >= size).count())
+            .sum();
+        let expected = (size * size) as f64 * p_out;
+        assert!((across as f64 - expected).abs() < 0.1 * expected);
+        let within = g.nedges() - across;
+        let expected = 2.0 * (size * (size - 1) / 2) as f64 * p_in;
+        assert!((within as f64 - expected).abs() < 0.1 * expected);
+    }
+}
@@ -1,17 +1,53 @@
 //! Compact graph data structure.
 
-use std::u32;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
 
+use rand::Rng;
 use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use rayon::slice::ParallelSlice;
+use rayon::slice::ParallelSliceMut;
+use serde_json::json;
+
+use crate::{seeded_rng, thread_seed, SummaryStats};
 
 pub(crate) type Vertex = u32;
 
+/// Errors from operations combining or constructing graphs.
+#[derive(Debug)]
+pub enum GraphError {
+    /// Two graphs which should share a vertex set have different sizes.
+    VertexCountMismatch { left: usize, right: usize },
+    /// The operation needs a connected graph.
+    Disconnected,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VertexCountMismatch { left, right } => {
+                write!(f, "vertex count mismatch: {} != {}", left, right)
+            }
+            Self::Disconnected => write!(f, "graph is not connected"),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
 /// A compact adjacency list intended for sparse graphs.
 ///
 /// The space of vertices is a contiguous range of u32 ints
 /// from [0, nvertices).
+#[derive(Debug, PartialEq, Eq)]
 pub struct Graph {
     offsets: Vec<usize>,
     neighbors: Vec<Vertex>,
@@ -21,21 +57,177 @@ impl Graph {
     /// `offsets.len()` should be one greater than the number of vertices
     /// with `neighbors[offsets[i]..offsets[i+1]]` being the edges incident
     /// from `i`, which should be necessarily sorted and bidirectional.
+    ///
+    /// In debug builds, [`Graph::dedup_edges`] and [`Graph::remove_self_loops`]
+    /// are run over a copy of the input to check that they'd change nothing.
     pub(crate) fn new(offsets: Vec<usize>, neighbors: Vec<Vertex>) -> Self {
         assert!(offsets.len() <= (1 << 32));
+        debug_assert!(offsets
+            .par_windows(2)
+            .all(|s| s[0] <= s[1] && neighbors[s[0]..s[1]].windows(2).all(|ss| ss[0] <= ss[1])));
+        #[cfg(debug_assertions)]
+        {
+            let (deduped, _) = Self::dedup_edges(offsets.clone(), neighbors.clone());
+            assert!(deduped == offsets, "duplicate edges");
+            let (looped, _) = Self::remove_self_loops(offsets.clone(), neighbors.clone());
+            assert!(looped == offsets, "self loops");
+        }
         debug_assert!(offsets.par_windows(2).enumerate().all(|(i, s)| {
-            s[0] < s[1]
-                && neighbors[s[0]..s[1]].windows(2).all(|ss| ss[0] < ss[1])
-                && neighbors[s[0]..s[1]].iter().copied().all(|j| {
-                    let i = &(i as u32);
-                    neighbors[offsets[j as usize]..offsets[1 + j as usize]]
-                        .binary_search(i)
-                        .is_ok()
-                })
+            let i = &(i as u32);
+            neighbors[s[0]..s[1]].iter().copied().all(|j| {
+                neighbors[offsets[j as usize]..offsets[1 + j as usize]]
+                    .binary_search(i)
+                    .is_ok()
+            })
         }));
         Self { offsets, neighbors }
     }
 
+    /// Removes repeated neighbors from each of the sorted neighbor lists in a
+    /// compressed layout, where `neighbors[offsets[v]..offsets[v + 1]]` are
+    /// the neighbors of `v`, returning the compacted layout.
+    pub fn dedup_edges(offsets: Vec<usize>, neighbors: Vec<Vertex>) -> (Vec<usize>, Vec<Vertex>) {
+        Self::retain_neighbors(offsets, neighbors, |_, last, w| last != Some(w))
+    }
+
+    /// Removes each vertex from its own neighbor list in the compressed
+    /// layout of [`Graph::dedup_edges`], returning the compacted layout.
+    pub fn remove_self_loops(
+        offsets: Vec<usize>,
+        neighbors: Vec<Vertex>,
+    ) -> (Vec<usize>, Vec<Vertex>) {
+        Self::retain_neighbors(offsets, neighbors, |v, _, w| w != v)
+    }
+
+    /// Keeps each neighbor `w` of `v` where `keep(v, last, w)`, given the last
+    /// neighbor of `v` kept so far, compacting the lists in place in O(n + m).
+    fn retain_neighbors<F>(
+        mut offsets: Vec<usize>,
+        mut neighbors: Vec<Vertex>,
+        keep: F,
+    ) -> (Vec<usize>, Vec<Vertex>)
+    where
+        F: Fn(Vertex, Option<Vertex>, Vertex) -> bool,
+    {
+        let mut write = 0;
+        let mut lo = offsets[0];
+        for v in 0..offsets.len() - 1 {
+            let hi = offsets[v + 1];
+            let start = write;
+            for read in lo..hi {
+                let last = if write > start {
+                    Some(neighbors[write - 1])
+                } else {
+                    None
+                };
+                let w = neighbors[read];
+                if keep(v as Vertex, last, w) {
+                    neighbors[write] = w;
+                    write += 1;
+                }
+            }
+            offsets[v + 1] = write;
+            lo = hi;
+        }
+        neighbors.truncate(write);
+        (offsets, neighbors)
+    }
+
+    /// Builds a graph from per-vertex neighbor lists, which should satisfy
+    /// the same sortedness and symmetry requirements as [`Graph::new`].
+    pub(crate) fn from_lists(lists: Vec<Vec<Vertex>>) -> Self {
+        let mut offsets = Vec::with_capacity(lists.len() + 1);
+        let mut neighbors = Vec::with_capacity(lists.iter().map(Vec::len).sum());
+        offsets.push(0);
+        for list in lists {
+            neighbors.extend_from_slice(&list);
+            offsets.push(neighbors.len());
+        }
+        Self::new(offsets, neighbors)
+    }
+
+    /// Builds a graph over `[0, nvertices)` from undirected edges, which may
+    /// be repeated or listed in either direction. Self loops are dropped, and
+    /// an edge outside the vertex range panics.
+    pub fn from_edge_pairs(nvertices: usize, edges: &[(Vertex, Vertex)]) -> Self {
+        let mut lists = vec![Vec::new(); nvertices];
+        for &(u, v) in edges {
+            assert!(
+                (u.max(v) as usize) < nvertices,
+                "edge ({}, {}) out of range for {} vertices",
+                u,
+                v,
+                nvertices
+            );
+            if u != v {
+                lists[u as usize].push(v);
+                lists[v as usize].push(u);
+            }
+        }
+        lists.par_iter_mut().for_each(|list| {
+            list.sort_unstable();
+            list.dedup();
+        });
+        Self::from_lists(lists)
+    }
+
+    /// The complete graph on `n` vertices.
+    pub fn complete(n: u32) -> Self {
+        Self::from_lists(
+            (0..n)
+                .map(|v| (0..n).filter(|&w| w != v).collect())
+                .collect(),
+        )
+    }
+
+    /// Builds a graph from a square, symmetric boolean adjacency matrix with a
+    /// `false` diagonal, panicking if the matrix is malformed.
+    ///
+    /// Mostly handy for writing out small known graphs by hand.
+    pub fn from_adjacency_matrix(matrix: &[&[bool]]) -> Self {
+        let n = matrix.len();
+        for (i, row) in matrix.iter().enumerate() {
+            assert!(
+                row.len() == n,
+                "row {} has length {} != {}",
+                i,
+                row.len(),
+                n
+            );
+            assert!(!row[i], "self-loop at {}", i);
+            for (j, &adjacent) in row.iter().enumerate() {
+                assert!(adjacent == matrix[j][i], "asymmetric at ({}, {})", i, j);
+            }
+        }
+        let lists = matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(_, &adjacent)| adjacent)
+                    .map(|(j, _)| j as Vertex)
+                    .collect()
+            })
+            .collect();
+        Self::from_lists(lists)
+    }
+
+    /// Returns the dense `nvertices` by `nvertices` adjacency matrix, which is
+    /// only sensible for small graphs, e.g., when debugging.
+    #[cfg(feature = "debug-matrix")]
+    pub fn adjacency_matrix(&self) -> Vec<Vec<bool>> {
+        let n = self.nvertices();
+        (0..n)
+            .map(|v| {
+                let mut row = vec![false; n];
+                for &w in self.neighbors(v as Vertex) {
+                    row[w as usize] = true;
+                }
+                row
+            })
+            .collect()
+    }
+
     pub fn neighbors(&self, v: Vertex) -> &[Vertex] {
         let v = v as usize;
         let lo = self.offsets[v];
@@ -50,6 +242,18 @@ impl Graph {
         hi - lo
     }
 
+    /// Whether `(u, v)` is an edge, by binary search over the neighbors of
+    /// `u`, in O(log degree).
+    pub fn has_edge(&self, u: Vertex, v: Vertex) -> bool {
+        self.neighbors(u).binary_search(&v).is_ok()
+    }
+
+    /// Number of vertices adjacent to both `u` and `v`, by merging their
+    /// neighbor lists in O(degree).
+    pub fn common_neighbor_count(&self, u: Vertex, v: Vertex) -> usize {
+        sorted_intersection_size(self.neighbors(u), self.neighbors(v))
+    }
+
     pub fn nvertices(&self) -> usize {
         self.offsets.len() - 1
     }
@@ -57,4 +261,2187 @@ impl Graph {
     pub fn nedges(&self) -> usize {
         self.neighbors.len() / 2
     }
+
+    /// Iterates over every undirected edge exactly once, as `(v, w)` with
+    /// `v < w`, in lexicographic order.
+    pub fn edges(&self) -> impl Iterator<Item = (Vertex, Vertex)> + '_ {
+        (0..self.nvertices()).flat_map(move |v| self.upper_edges(v as Vertex))
+    }
+
+    /// Parallel version of [`Graph::edges`], without any ordering guarantees.
+    pub fn edges_par(&self) -> impl ParallelIterator<Item = (Vertex, Vertex)> + '_ {
+        (0..self.nvertices())
+            .into_par_iter()
+            .flat_map_iter(move |v| self.upper_edges(v as Vertex))
+    }
+
+    /// Edges `(v, w)` incident to `v` with `v < w`.
+    fn upper_edges(&self, v: Vertex) -> impl Iterator<Item = (Vertex, Vertex)> + '_ {
+        let neighbors = self.neighbors(v);
+        let start = neighbors.partition_point(|&w| w <= v);
+        neighbors[start..].iter().map(move |&w| (v, w))
+    }
+
+    /// Returns the complement graph, in which `(u, v)` is an edge iff `u != v`
+    /// and `(u, v)` isn't an edge here.
+    ///
+    /// Each new neighbor list is the set difference of `[0, n)` with the old
+    /// one (and the vertex itself), computed in parallel over vertices, which
+    /// is linear in the sizes of the input and output.
+    pub fn complement(&self) -> Self {
+        let n = self.nvertices() as Vertex;
+        let lists = (0..n)
+            .into_par_iter()
+            .map(|v| {
+                let neighbors = self.neighbors(v);
+                let mut out = Vec::with_capacity(n as usize - 1 - neighbors.len());
+                let mut next = 0;
+                for w in neighbors.iter().copied().chain(std::iter::once(n)) {
+                    out.extend((next..w).filter(|&u| u != v));
+                    next = w + 1;
+                }
+                out
+            })
+            .collect();
+        Self::from_lists(lists)
+    }
+
+    /// Returns the sorted vertices within two hops of `v`, other than `v`.
+    pub fn two_hop_neighborhood(&self, v: Vertex) -> Vec<Vertex> {
+        let mut out: Vec<Vertex> = self.neighbors(v).to_vec();
+        for &w in self.neighbors(v) {
+            out.extend(self.neighbors(w).iter().copied().filter(|&u| u != v));
+        }
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Returns how many vertices other than `v` are within `radius` hops of
+    /// it, so a radius of one gives `v`'s degree.
+    pub fn neighborhood_size(&self, v: Vertex, radius: u32) -> usize {
+        self.within_radius(v, radius).len() - 1
+    }
+
+    /// Returns the subgraph induced by `v` and every vertex within `radius`
+    /// hops of it, keeping the relative order of vertex ids.
+    pub fn ego_network(&self, v: Vertex, radius: u32) -> Self {
+        let mut vertices = self.within_radius(v, radius);
+        vertices.sort_unstable();
+        self.induced_subgraph(&vertices)
+    }
+
+    /// The `k`-th power of the graph, with an edge between every two
+    /// distinct vertices at most `k` hops apart.
+    ///
+    /// Runs a BFS limited to `k` levels from every vertex in parallel, which
+    /// takes O(n * (n + m)) time in the worst case, and the result can be
+    /// much denser than the graph itself.
+    pub fn power(&self, k: u32) -> Self {
+        let lists = (0..self.nvertices() as Vertex)
+            .into_par_iter()
+            .map(|v| {
+                let mut list = self.within_radius(v, k);
+                list.swap_remove(0);
+                list.sort_unstable();
+                list
+            })
+            .collect();
+        Self::from_lists(lists)
+    }
+
+    /// Returns the line graph, with a vertex for each edge, adjacent when
+    /// the edges share an endpoint, along with the edge of this graph each of
+    /// its vertices stands for, where vertex `i` is the `i`-th edge of
+    /// [`Graph::edges`].
+    ///
+    /// A proper vertex coloring of the line graph is a proper edge coloring
+    /// of this one.
+    pub fn line_graph(&self) -> (Self, Vec<(Vertex, Vertex)>) {
+        let edges: Vec<(Vertex, Vertex)> = self.edges().collect();
+
+        // the line graph vertex standing for each entry of `neighbors`
+        let mut entry_edges = vec![0 as Vertex; self.neighbors.len()];
+        let mut next = 0;
+        for v in 0..self.nvertices() {
+            let (lo, hi) = (self.offsets[v], self.offsets[v + 1]);
+            for (entry, &w) in entry_edges[lo..hi].iter_mut().zip(&self.neighbors[lo..hi]) {
+                if w as usize > v {
+                    *entry = next;
+                    next += 1;
+                }
+            }
+        }
+        for v in 0..self.nvertices() {
+            for i in self.offsets[v]..self.offsets[v + 1] {
+                let w = self.neighbors[i];
+                if (w as usize) < v {
+                    let j = self.neighbors(w).binary_search(&(v as Vertex)).unwrap();
+                    entry_edges[i] = entry_edges[self.offsets[w as usize] + j];
+                }
+            }
+        }
+
+        let lists = edges
+            .par_iter()
+            .enumerate()
+            .map(|(e, &(u, v))| {
+                let incident = |x: Vertex| {
+                    let x = x as usize;
+                    entry_edges[self.offsets[x]..self.offsets[x + 1]].iter()
+                };
+                let mut list: Vec<Vertex> = incident(u)
+                    .chain(incident(v))
+                    .copied()
+                    .filter(|&f| f as usize != e)
+                    .collect();
+                list.sort_unstable();
+                list
+            })
+            .collect();
+        (Self::from_lists(lists), edges)
+    }
+
+    /// Contracts each group of vertices into a single vertex, where vertex
+    /// `v` belongs to group `groups[v]`, out of groups `[0, max group]`.
+    /// Two groups are adjacent if any of their members are, and edges within
+    /// a group disappear.
+    ///
+    /// Members are bucketed by group with a counting sort, and then each
+    /// group's neighbor list is built in parallel, in O(n + m log m) overall.
+    pub fn contract(&self, groups: &[u32]) -> Self {
+        assert!(
+            groups.len() == self.nvertices(),
+            "{} groups for {} vertices",
+            groups.len(),
+            self.nvertices()
+        );
+        let ngroups = groups.iter().map(|&g| g as usize + 1).max().unwrap_or(0);
+        let mut starts = vec![0; ngroups + 1];
+        for &g in groups {
+            starts[g as usize + 1] += 1;
+        }
+        for g in 0..ngroups {
+            starts[g + 1] += starts[g];
+        }
+        let mut members = vec![0 as Vertex; groups.len()];
+        let mut next = starts.clone();
+        for (v, &g) in groups.iter().enumerate() {
+            members[next[g as usize]] = v as Vertex;
+            next[g as usize] += 1;
+        }
+
+        let lists = (0..ngroups)
+            .into_par_iter()
+            .map(|g| {
+                let mut list: Vec<Vertex> = members[starts[g]..starts[g + 1]]
+                    .iter()
+                    .flat_map(|&v| self.neighbors(v).iter().map(|&w| groups[w as usize]))
+                    .filter(|&h| h as usize != g)
+                    .collect();
+                list.sort_unstable();
+                list.dedup();
+                list
+            })
+            .collect();
+        Self::from_lists(lists)
+    }
+
+    /// The vertices within `radius` hops of `v`, including `v`, in BFS order,
+    /// from a BFS which stops after `radius` levels.
+    fn within_radius(&self, v: Vertex, radius: u32) -> Vec<Vertex> {
+        let mut seen: HashSet<Vertex> = HashSet::new();
+        seen.insert(v);
+        let mut reached = vec![v];
+        let mut level_start = 0;
+        for _ in 0..radius {
+            let level_end = reached.len();
+            for i in level_start..level_end {
+                for &w in self.neighbors(reached[i]) {
+                    if seen.insert(w) {
+                        reached.push(w);
+                    }
+                }
+            }
+            if reached.len() == level_end {
+                break;
+            }
+            level_start = level_end;
+        }
+        reached
+    }
+
+    /// Summarizes [`Graph::neighborhood_size`] with the given `radius` over
+    /// every vertex.
+    pub fn neighborhood_distribution(&self, radius: u32) -> SummaryStats {
+        SummaryStats::from_parallel(
+            (0..self.nvertices() as Vertex)
+                .into_par_iter()
+                .map(|v| self.neighborhood_size(v, radius) as f64),
+        )
+    }
+
+    /// Returns the subgraph induced by the distinct `vertices`, where vertex
+    /// `i` of the subgraph is `vertices[i]`.
+    pub fn induced_subgraph(&self, vertices: &[Vertex]) -> Self {
+        let mut relabel = vec![UNREACHED; self.nvertices()];
+        for (i, &v) in vertices.iter().enumerate() {
+            assert!(relabel[v as usize] == UNREACHED, "duplicate vertex {}", v);
+            relabel[v as usize] = i as Vertex;
+        }
+        let lists = vertices
+            .par_iter()
+            .map(|&v| {
+                let mut out: Vec<Vertex> = self
+                    .neighbors(v)
+                    .iter()
+                    .map(|&w| relabel[w as usize])
+                    .filter(|&w| w != UNREACHED)
+                    .collect();
+                out.sort_unstable();
+                out
+            })
+            .collect();
+        Self::from_lists(lists)
+    }
+
+    /// Returns the subgraph induced by the vertices `v` with `mask[v]`, keeping
+    /// their relative order.
+    pub fn subgraph_by_vertices(&self, mask: &[bool]) -> Self {
+        assert!(mask.len() == self.nvertices());
+        let vertices: Vec<Vertex> = (0..self.nvertices() as Vertex)
+            .filter(|&v| mask[v as usize])
+            .collect();
+        self.induced_subgraph(&vertices)
+    }
+
+    /// Whether every vertex can reach every other, which holds vacuously for
+    /// graphs with at most one vertex.
+    pub fn is_connected(&self) -> bool {
+        self.nvertices() <= 1 || bfs_distances(self, 0).iter().all(|&d| d != UNREACHED)
+    }
+
+    /// Returns the subgraph induced by the largest connected component, with
+    /// ties going to the component of the smallest vertex, and vertices
+    /// relabeled in increasing order.
+    pub fn largest_connected_component(&self) -> Self {
+        let labels = connected_components(self);
+        let ncomponents = labels.iter().max().map_or(0, |&c| c as usize + 1);
+        let mut sizes = vec![0usize; ncomponents];
+        for &c in &labels {
+            sizes[c as usize] += 1;
+        }
+        let largest = (0..ncomponents)
+            .rev()
+            .max_by_key(|&c| sizes[c])
+            .map_or(0, |c| c as u32);
+        let vertices: Vec<Vertex> = (0..self.nvertices() as Vertex)
+            .filter(|&v| labels[v as usize] == largest)
+            .collect();
+        self.induced_subgraph(&vertices)
+    }
+
+    /// Largest vertex degree, or zero for a graph without vertices.
+    pub fn max_degree(&self) -> usize {
+        self.offsets
+            .par_windows(2)
+            .map(|s| s[1] - s[0])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Smallest vertex degree, or zero for a graph without vertices.
+    pub fn min_degree(&self) -> usize {
+        self.offsets
+            .par_windows(2)
+            .map(|s| s[1] - s[0])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Mean vertex degree, `2 * nedges / nvertices`, or zero for a graph
+    /// without vertices.
+    pub fn avg_degree(&self) -> f64 {
+        if self.nvertices() == 0 {
+            return 0.0;
+        }
+        2.0 * self.nedges() as f64 / self.nvertices() as f64
+    }
+
+    /// Returns every vertex sorted by degree, in non-increasing order if
+    /// `descending` and non-decreasing order otherwise, with ties broken by
+    /// increasing id either way.
+    pub fn vertices_by_degree(&self, descending: bool) -> Vec<Vertex> {
+        let mut vertices: Vec<Vertex> = (0..self.nvertices() as Vertex).collect();
+        if descending {
+            vertices.par_sort_by_key(|&v| Reverse(self.degree(v)));
+        } else {
+            vertices.par_sort_by_key(|&v| self.degree(v));
+        }
+        vertices
+    }
+
+    /// Iterates over the vertices in the order of
+    /// [`Graph::vertices_by_degree`], without comparison sorting.
+    ///
+    /// The vertices are bucketed by degree once, in O(n + max degree) time,
+    /// by a counting sort whose bucket offsets are the prefix sums of the
+    /// [`Graph::degree_histogram`], and the buckets are then visited in
+    /// degree order.
+    pub fn vertices_by_degree_iter(&self, descending: bool) -> impl Iterator<Item = Vertex> + '_ {
+        let histogram = self.degree_histogram();
+        let mut starts = Vec::with_capacity(histogram.len() + 1);
+        starts.push(0);
+        for count in &histogram {
+            starts.push(starts.last().unwrap() + count);
+        }
+        let mut next = starts.clone();
+        let mut buckets = vec![0; self.nvertices()];
+        for v in 0..self.nvertices() as Vertex {
+            let d = self.degree(v);
+            buckets[next[d]] = v;
+            next[d] += 1;
+        }
+        let ndegrees = histogram.len();
+        (0..ndegrees)
+            .map(move |i| if descending { ndegrees - 1 - i } else { i })
+            .flat_map(move |d| starts[d]..starts[d + 1])
+            .map(move |i| buckets[i])
+    }
+
+    /// Returns the degree distribution, where index `d` holds the number of
+    /// vertices with degree `d`, of length `max_degree() + 1`.
+    pub fn degree_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.max_degree() + 1];
+        for s in self.offsets.windows(2) {
+            histogram[s[1] - s[0]] += 1;
+        }
+        histogram
+    }
+}
+
+/// A [`Graph`] with a weight on every edge.
+#[derive(Debug, PartialEq)]
+pub struct WeightedGraph {
+    inner: Graph,
+    /// `weights[i]` is the weight of the edge to `inner.neighbors[i]`.
+    weights: Vec<f32>,
+}
+
+impl WeightedGraph {
+    /// Takes the same `offsets` and `neighbors` as [`Graph::new`], along
+    /// with a weight for each entry of `neighbors`, which should agree for
+    /// both directions of an edge.
+    pub(crate) fn new(offsets: Vec<usize>, neighbors: Vec<Vertex>, weights: Vec<f32>) -> Self {
+        assert!(
+            weights.len() == neighbors.len(),
+            "{} weights for {} neighbors",
+            weights.len(),
+            neighbors.len()
+        );
+        let weighted = Self {
+            inner: Graph::new(offsets, neighbors),
+            weights,
+        };
+        debug_assert!(weighted.inner.edges().all(|(u, v)| {
+            weighted.edge_weight(u, v).map(f32::to_bits)
+                == weighted.edge_weight(v, u).map(f32::to_bits)
+        }));
+        weighted
+    }
+
+    /// The weight of edge `(u, v)`, or `None` if there's no such edge.
+    pub fn edge_weight(&self, u: Vertex, v: Vertex) -> Option<f32> {
+        let lo = self.inner.offsets[u as usize];
+        self.inner
+            .neighbors(u)
+            .binary_search(&v)
+            .ok()
+            .map(|i| self.weights[lo + i])
+    }
+
+    /// The weights of the edges to [`Graph::neighbors`] of `v`, in the same
+    /// order.
+    pub fn neighbor_weights(&self, v: Vertex) -> &[f32] {
+        let v = v as usize;
+        &self.weights[self.inner.offsets[v]..self.inner.offsets[v + 1]]
+    }
+
+    pub fn as_unweighted(&self) -> &Graph {
+        &self.inner
+    }
+}
+
+/// A [`Graph`] with a distinct label, such as an external name, for every
+/// vertex.
+#[derive(Debug)]
+pub struct LabeledGraph<L> {
+    inner: Graph,
+    labels: Vec<L>,
+    index: HashMap<L, Vertex>,
+}
+
+impl<L: Eq + Hash + Clone> LabeledGraph<L> {
+    /// Labels vertex `v` of `graph` with `labels[v]`, panicking unless there
+    /// is exactly one distinct label per vertex.
+    pub fn new(graph: Graph, labels: Vec<L>) -> Self {
+        assert!(
+            labels.len() == graph.nvertices(),
+            "{} labels for {} vertices",
+            labels.len(),
+            graph.nvertices()
+        );
+        let index: HashMap<L, Vertex> = labels
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(v, label)| (label, v as Vertex))
+            .collect();
+        assert!(index.len() == labels.len(), "duplicate vertex labels");
+        Self {
+            inner: graph,
+            labels,
+            index,
+        }
+    }
+
+    pub fn label(&self, v: Vertex) -> &L {
+        &self.labels[v as usize]
+    }
+
+    /// The vertex with the given label, if any.
+    pub fn vertex_by_label(&self, label: &L) -> Option<Vertex> {
+        self.index.get(label).copied()
+    }
+
+    pub fn as_unlabeled(&self) -> &Graph {
+        &self.inner
+    }
+}
+
+/// Returns the graphs obtained by deleting the vertices in `order` one at a
+/// time, i.e., the `i`-th returned graph has `order[..=i]` removed.
+///
+/// Deleted vertices keep their ids but lose all of their incident edges, so
+/// every graph in the sequence is over the same `[0, nvertices)` vertex space.
+///
+/// This is O(|order| * (n + m)) and is meant for research and visualization
+/// on smaller graphs.
+pub fn vertex_deletion_sequence(graph: &Graph, order: &[Vertex]) -> Vec<Graph> {
+    let mut deleted = vec![false; graph.nvertices()];
+    order
+        .iter()
+        .map(|&v| {
+            deleted[v as usize] = true;
+            let deleted = &deleted;
+            let lists = (0..graph.nvertices())
+                .into_par_iter()
+                .map(|u| {
+                    if deleted[u] {
+                        return Vec::new();
+                    }
+                    graph
+                        .neighbors(u as Vertex)
+                        .iter()
+                        .copied()
+                        .filter(|&w| !deleted[w as usize])
+                        .collect()
+                })
+                .collect();
+            Graph::from_lists(lists)
+        })
+        .collect()
+}
+
+/// Returns the normalized cut `|E(A, B)| / vol(A) + |E(A, B)| / vol(B)` of the
+/// 2-way partition where `partition[v]` is `0` if `v` is in `A` and `1` if `v`
+/// is in `B`.
+pub fn normalized_cut(graph: &Graph, partition: &[u32]) -> f64 {
+    normalized_cut_k(graph, partition, 2)
+}
+
+/// Returns the `k`-way normalized cut `sum_c |E(P_c, V - P_c)| / vol(P_c)`,
+/// where `P_c` are the vertices `v` with `partition[v] == c` and `vol` is the
+/// sum of degrees. Parts with no volume contribute nothing.
+pub fn normalized_cut_k(graph: &Graph, partition: &[u32], k: u32) -> f64 {
+    assert!(partition.len() == graph.nvertices());
+    let k = k as usize;
+    let (cut, vol) = (0..graph.nvertices())
+        .into_par_iter()
+        .fold(
+            || (vec![0usize; k], vec![0usize; k]),
+            |(mut cut, mut vol), v| {
+                let c = partition[v] as usize;
+                let neighbors = graph.neighbors(v as Vertex);
+                vol[c] += neighbors.len();
+                cut[c] += neighbors
+                    .iter()
+                    .filter(|&&w| partition[w as usize] as usize != c)
+                    .count();
+                (cut, vol)
+            },
+        )
+        .reduce(
+            || (vec![0usize; k], vec![0usize; k]),
+            |(mut cut, mut vol), (other_cut, other_vol)| {
+                cut.iter_mut().zip(other_cut).for_each(|(a, b)| *a += b);
+                vol.iter_mut().zip(other_vol).for_each(|(a, b)| *a += b);
+                (cut, vol)
+            },
+        );
+    cut.into_iter()
+        .zip(vol)
+        .filter(|&(_, vol)| vol > 0)
+        .map(|(cut, vol)| cut as f64 / vol as f64)
+        .sum()
+}
+
+/// Same as [`Graph::common_neighbor_count`].
+pub fn common_neighbors(graph: &Graph, u: Vertex, v: Vertex) -> usize {
+    graph.common_neighbor_count(u, v)
+}
+
+/// The Jaccard similarity `|N(u) ∩ N(v)| / |N(u) ∪ N(v)|` of the
+/// neighborhoods of `u` and `v`, or zero if both are isolated.
+pub fn jaccard_similarity(graph: &Graph, u: Vertex, v: Vertex) -> f64 {
+    let common = graph.common_neighbor_count(u, v);
+    let union = graph.degree(u) + graph.degree(v) - common;
+    if union == 0 {
+        0.0
+    } else {
+        common as f64 / union as f64
+    }
+}
+
+/// Computes [`jaccard_similarity`] in parallel for each edge, in the order
+/// of [`Graph::edges`].
+pub fn all_pairs_jaccard_for_edges(graph: &Graph) -> Vec<f64> {
+    (0..graph.nvertices())
+        .into_par_iter()
+        .flat_map_iter(|v| graph.upper_edges(v as Vertex))
+        .map(|(u, v)| jaccard_similarity(graph, u, v))
+        .collect()
+}
+
+/// Returns the size of the intersection of two sorted vertex lists.
+pub(crate) fn sorted_intersection_size(a: &[Vertex], b: &[Vertex]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Number of triangles containing `v`.
+fn vertex_triangles(graph: &Graph, v: Vertex) -> usize {
+    let neighbors = graph.neighbors(v);
+    neighbors
+        .iter()
+        .map(|&w| sorted_intersection_size(neighbors, graph.neighbors(w)))
+        .sum::<usize>()
+        / 2
+}
+
+fn choose2(n: usize) -> usize {
+    n * n.saturating_sub(1) / 2
+}
+
+/// Returns the local clustering coefficient of every vertex, which is the
+/// fraction of pairs of its neighbors which are themselves adjacent.
+///
+/// Vertices of degree less than two have a coefficient of zero.
+pub fn local_clustering(graph: &Graph) -> Vec<f64> {
+    (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let v = v as Vertex;
+            let pairs = choose2(graph.degree(v));
+            if pairs == 0 {
+                0.0
+            } else {
+                vertex_triangles(graph, v) as f64 / pairs as f64
+            }
+        })
+        .collect()
+}
+
+/// Returns the mean local clustering coefficient over the vertices of degree
+/// at least two, whose coefficient is well-defined, or zero if there are none.
+pub fn average_clustering_coefficient(graph: &Graph) -> f64 {
+    let local = local_clustering(graph);
+    let (sum, count) = local
+        .into_par_iter()
+        .enumerate()
+        .filter(|&(v, _)| graph.degree(v as Vertex) >= 2)
+        .map(|(_, c)| (c, 1usize))
+        .reduce(|| (0.0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+/// Returns the transitivity `3 * triangles / triads`, where a triad is a path
+/// of length two, or zero if there are no triads.
+pub fn transitivity(graph: &Graph) -> f64 {
+    let triads: usize = (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| choose2(graph.degree(v as Vertex)))
+        .sum();
+    if triads == 0 {
+        0.0
+    } else {
+        3.0 * oriented_triangle_count(graph) as f64 / triads as f64
+    }
+}
+
+/// Returns the global clustering coefficient, the ratio of (three times) the
+/// number of triangles to the number of connected triples. This is the same
+/// quantity as [`transitivity`].
+pub fn clustering_coefficient(graph: &Graph) -> f64 {
+    transitivity(graph)
+}
+
+/// Returns the exact number of triangles in the graph.
+///
+/// For every edge `(u, v)`, oriented from lower to higher `(degree, id)`, this
+/// counts the common out-neighbors of `u` and `v` with a two-pointer merge.
+pub fn count_triangles(graph: &Graph) -> u64 {
+    let triangle_start = Instant::now();
+    let triangles = oriented_triangle_count(graph);
+    println!(
+        "{}",
+        json!({
+            "triangles": triangles,
+            "triangle_count_time": format!("{:.0?}", Instant::now().duration_since(triangle_start)),
+        })
+    );
+    triangles
+}
+
+/// Counts triangles by orienting each edge towards its endpoint of higher
+/// `(degree, id)` rank and intersecting the sorted out-neighborhoods, so that
+/// each triangle is found exactly once, in O(m * sqrt(m)) time.
+fn oriented_triangle_count(graph: &Graph) -> u64 {
+    let rank = |v: Vertex| (graph.degree(v), v);
+    let forward: Vec<Vec<Vertex>> = (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let v = v as Vertex;
+            graph
+                .neighbors(v)
+                .iter()
+                .copied()
+                .filter(|&w| rank(w) > rank(v))
+                .collect()
+        })
+        .collect();
+    forward
+        .par_iter()
+        .map(|out| {
+            out.iter()
+                .map(|&w| sorted_intersection_size(out, &forward[w as usize]) as u64)
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+/// Counts of small subgraphs, which need not be induced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MotifCounts {
+    pub triangles: u64,
+    pub four_cycles: u64,
+    /// Paths with three edges through four distinct vertices.
+    pub paths3: u64,
+}
+
+/// Counts the triangles, 4-cycles, and 3-edge paths in the graph.
+///
+/// Triangles are counted as in [`count_triangles`]. Every 4-cycle has two
+/// diagonals, each of whose endpoints share two neighbors on the cycle, so
+/// 4-cycles are half the sum over vertex pairs `u < w` of
+/// `choose2(common neighbors)`, found by enumerating the 2-paths from each
+/// `u` in parallel. A 3-edge path is a middle edge `(u, v)` extended at both
+/// ends, except where both ends are the same vertex, which closes a
+/// triangle; each triangle is overcounted this way three times.
+pub fn motif_counts(graph: &Graph) -> MotifCounts {
+    let start = Instant::now();
+    let triangles = oriented_triangle_count(graph);
+    let diagonal_cycles: u64 = (0..graph.nvertices() as Vertex)
+        .into_par_iter()
+        .map(|u| {
+            let mut common: HashMap<Vertex, usize> = HashMap::new();
+            for &v in graph.neighbors(u) {
+                for &w in graph.neighbors(v).iter().filter(|&&w| w > u) {
+                    *common.entry(w).or_default() += 1;
+                }
+            }
+            common.values().map(|&c| choose2(c) as u64).sum::<u64>()
+        })
+        .sum();
+    let extended_edges: u64 = graph
+        .edges()
+        .map(|(u, v)| ((graph.degree(u) - 1) * (graph.degree(v) - 1)) as u64)
+        .sum();
+    let counts = MotifCounts {
+        triangles,
+        four_cycles: diagonal_cycles / 2,
+        paths3: extended_edges - 3 * triangles,
+    };
+    println!(
+        "{}",
+        json!({
+            "triangles": counts.triangles,
+            "four_cycles": counts.four_cycles,
+            "paths3": counts.paths3,
+            "motif_count_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    counts
+}
+
+/// Returns the sorted cut vertices, whose removal increases the number of
+/// connected components.
+pub fn articulation_points(graph: &Graph) -> Vec<Vertex> {
+    let (is_articulation, _) = lowlink(graph);
+    is_articulation
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, a)| a)
+        .map(|(v, _)| v as Vertex)
+        .collect()
+}
+
+/// Returns the sorted bridges `(u, v)`, with `u < v`, whose removal increases
+/// the number of connected components.
+pub fn bridges(graph: &Graph) -> Vec<(Vertex, Vertex)> {
+    let (_, mut bridges) = lowlink(graph);
+    bridges.sort_unstable();
+    bridges
+}
+
+/// Runs Tarjan's lowlink DFS over every component, returning which vertices
+/// are articulation points along with the (unsorted) bridges.
+///
+/// The DFS keeps an explicit stack of `(vertex, next neighbor index)` frames
+/// rather than recursing, so deep graphs such as long paths don't overflow.
+fn lowlink(graph: &Graph) -> (Vec<bool>, Vec<(Vertex, Vertex)>) {
+    const UNSEEN: u32 = u32::MAX;
+    let n = graph.nvertices();
+    let mut disc = vec![UNSEEN; n];
+    let mut low = vec![UNSEEN; n];
+    let mut parent = vec![UNSEEN; n];
+    let mut is_articulation = vec![false; n];
+    let mut bridges = Vec::new();
+    let mut time = 0;
+    let mut stack: Vec<(Vertex, usize)> = Vec::new();
+
+    for root in 0..n {
+        if disc[root] != UNSEEN {
+            continue;
+        }
+        disc[root] = time;
+        low[root] = time;
+        time += 1;
+        stack.push((root as Vertex, 0));
+        let mut root_children = 0;
+
+        while let Some((v, next)) = stack.last_mut() {
+            let v = *v;
+            let neighbors = graph.neighbors(v);
+            if *next < neighbors.len() {
+                let w = neighbors[*next];
+                *next += 1;
+                if disc[w as usize] == UNSEEN {
+                    parent[w as usize] = v;
+                    disc[w as usize] = time;
+                    low[w as usize] = time;
+                    time += 1;
+                    stack.push((w, 0));
+                } else if w != parent[v as usize] {
+                    low[v as usize] = low[v as usize].min(disc[w as usize]);
+                }
+                continue;
+            }
+
+            stack.pop();
+            if let Some(&(p, _)) = stack.last() {
+                let (p, v) = (p as usize, v as usize);
+                low[p] = low[p].min(low[v]);
+                if low[v] > disc[p] {
+                    bridges.push((p.min(v) as Vertex, p.max(v) as Vertex));
+                }
+                if p == root {
+                    root_children += 1;
+                } else if low[v] >= disc[p] {
+                    is_articulation[p] = true;
+                }
+            }
+        }
+        is_articulation[root] = root_children >= 2;
+    }
+
+    (is_articulation, bridges)
+}
+
+/// Returns the core number of every vertex, the largest `k` such that the
+/// vertex belongs to the `k`-core (the maximal subgraph of minimum degree `k`).
+///
+/// Uses the O(n + m) bucket-queue peeling of Batagelj and Zaversnik, which
+/// repeatedly removes a vertex of minimum remaining degree. Since every
+/// subgraph of a graph with max core number `k` has a vertex of degree at most
+/// `k`, greedy coloring in reverse peeling order uses at most `k + 1` colors.
+pub fn kcore_decomposition(graph: &Graph) -> Vec<u32> {
+    degeneracy_peeling(graph).1
+}
+
+/// Same as [`kcore_decomposition`].
+pub fn core_numbers(graph: &Graph) -> Vec<u32> {
+    kcore_decomposition(graph)
+}
+
+/// Returns the subgraph induced by the vertices with core number at least
+/// `min_k`, i.e., the `min_k`-core, with vertices relabeled in increasing
+/// order. Such dense regions are where Glauber updates conflict the most.
+pub fn high_core_subgraph(graph: &Graph, min_k: u32) -> Graph {
+    let vertices: Vec<Vertex> = core_numbers(graph)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, k)| k >= min_k)
+        .map(|(v, _)| v as Vertex)
+        .collect();
+    graph.induced_subgraph(&vertices)
+}
+
+/// Returns `(order, cores)`, the order in which the peeling of
+/// [`kcore_decomposition`] removes vertices, and their core numbers.
+pub(crate) fn degeneracy_peeling(graph: &Graph) -> (Vec<Vertex>, Vec<u32>) {
+    let n = graph.nvertices();
+    let mut degree: Vec<usize> = (0..n).map(|v| graph.degree(v as Vertex)).collect();
+
+    // bin[d] is where vertices of remaining degree d start in `order`
+    let mut bin = vec![0usize; graph.max_degree() + 1];
+    for &d in &degree {
+        bin[d] += 1;
+    }
+    let mut start = 0;
+    for b in bin.iter_mut() {
+        let count = *b;
+        *b = start;
+        start += count;
+    }
+    let mut order = vec![0 as Vertex; n];
+    let mut position = vec![0usize; n];
+    for v in 0..n {
+        position[v] = bin[degree[v]];
+        order[position[v]] = v as Vertex;
+        bin[degree[v]] += 1;
+    }
+    for d in (1..bin.len()).rev() {
+        bin[d] = bin[d - 1];
+    }
+    bin[0] = 0;
+
+    for i in 0..n {
+        let v = order[i] as usize;
+        for &u in graph.neighbors(v as Vertex) {
+            let u = u as usize;
+            if degree[u] > degree[v] {
+                // move u to the front of its bin, then shrink the bin past it
+                let du = degree[u];
+                let front = bin[du];
+                let w = order[front] as usize;
+                if u != w {
+                    order.swap(position[u], front);
+                    position[w] = position[u];
+                    position[u] = front;
+                }
+                bin[du] += 1;
+                degree[u] -= 1;
+            }
+        }
+    }
+
+    (order, degree.into_iter().map(|d| d as u32).collect())
+}
+
+/// Returns the sorted vertices of a maximal independent set, built by
+/// repeatedly taking a vertex of minimum degree among those remaining and
+/// removing it along with its neighbors. Ties are broken randomly, with the
+/// given seed.
+pub fn greedy_independent_set(graph: &Graph, seed: u64) -> Vec<Vertex> {
+    let n = graph.nvertices();
+    let mut rng = seeded_rng(seed);
+    let tiebreak: Vec<u64> = (0..n).map(|_| rng.gen()).collect();
+    let mut degree: Vec<usize> = (0..n).map(|v| graph.degree(v as Vertex)).collect();
+    let mut removed = vec![false; n];
+    let mut heap: BinaryHeap<Reverse<(usize, u64, Vertex)>> = (0..n)
+        .map(|v| Reverse((degree[v], tiebreak[v], v as Vertex)))
+        .collect();
+
+    let mut set = Vec::new();
+    while let Some(Reverse((d, _, v))) = heap.pop() {
+        // skip vertices already removed, or entries left over from before
+        // their degree last dropped
+        if removed[v as usize] || d != degree[v as usize] {
+            continue;
+        }
+        set.push(v);
+        removed[v as usize] = true;
+        for &u in graph.neighbors(v) {
+            if std::mem::replace(&mut removed[u as usize], true) {
+                continue;
+            }
+            for &x in graph.neighbors(u) {
+                if !removed[x as usize] {
+                    degree[x as usize] -= 1;
+                    heap.push(Reverse((degree[x as usize], tiebreak[x as usize], x)));
+                }
+            }
+        }
+    }
+    set.sort_unstable();
+    set
+}
+
+/// Returns the size of the largest clique found by greedy growth within
+/// `time_limit`, which lower-bounds both the clique and chromatic numbers.
+///
+/// Cliques are grown from each start vertex in decreasing degree order, by
+/// repeatedly adding the candidate with the most neighbors among the
+/// remaining candidates, where the candidates are the common neighbors of
+/// the clique so far. Starts whose degree can't beat the best clique are
+/// skipped. At least one start is always tried, even past the time limit.
+pub fn clique_lower_bound(graph: &Graph, time_limit: Duration) -> u32 {
+    let start = Instant::now();
+    let mut order: Vec<Vertex> = (0..graph.nvertices() as Vertex).collect();
+    order.sort_unstable_by_key(|&v| Reverse(graph.degree(v)));
+
+    let mut best = 0;
+    for (i, &v) in order.iter().enumerate() {
+        if graph.degree(v) < best || (i > 0 && start.elapsed() > time_limit) {
+            break;
+        }
+        let mut size = 1;
+        let mut candidates = graph.neighbors(v).to_vec();
+        while !candidates.is_empty() {
+            let u = *candidates
+                .iter()
+                .max_by_key(|&&u| sorted_intersection_size(&candidates, graph.neighbors(u)))
+                .unwrap();
+            candidates.retain(|&w| graph.has_edge(u, w));
+            size += 1;
+        }
+        best = best.max(size);
+    }
+
+    println!(
+        "{}",
+        json!({
+            "clique_lower_bound": best,
+            "clique_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    best as u32
+}
+
+/// Returns the connected component label of every vertex, where components
+/// are numbered from zero in order of their smallest vertex. Runs in O(n + m).
+pub fn connected_components(graph: &Graph) -> Vec<u32> {
+    let mut labels = vec![UNREACHED; graph.nvertices()];
+    let mut queue = VecDeque::new();
+    let mut ncomponents = 0;
+    for src in 0..graph.nvertices() as Vertex {
+        if labels[src as usize] != UNREACHED {
+            continue;
+        }
+        labels[src as usize] = ncomponents;
+        queue.push_back(src);
+        while let Some(v) = queue.pop_front() {
+            for &w in graph.neighbors(v) {
+                if labels[w as usize] == UNREACHED {
+                    labels[w as usize] = ncomponents;
+                    queue.push_back(w);
+                }
+            }
+        }
+        ncomponents += 1;
+    }
+    labels
+}
+
+/// Groups vertices into layers by their hop distance from the nearest of
+/// `sources`, with layer `0` holding the distinct sources themselves, each
+/// layer sorted. Vertices unreachable from every source are left out.
+///
+/// Edges only join vertices within a layer or in adjacent layers, so a layer
+/// an algorithm processes only needs the layers on either side of it.
+pub fn topological_generations(graph: &Graph, sources: &[Vertex]) -> Vec<Vec<Vertex>> {
+    let mut reached = vec![false; graph.nvertices()];
+    let mut layer: Vec<Vertex> = sources
+        .iter()
+        .copied()
+        .filter(|&v| !std::mem::replace(&mut reached[v as usize], true))
+        .collect();
+    let mut layers = Vec::new();
+    while !layer.is_empty() {
+        let mut next = Vec::new();
+        for &v in &layer {
+            for &w in graph.neighbors(v) {
+                if !reached[w as usize] {
+                    reached[w as usize] = true;
+                    next.push(w);
+                }
+            }
+        }
+        layer.sort_unstable();
+        layers.push(layer);
+        layer = next;
+    }
+    layers
+}
+
+/// Distance marker for vertices a BFS didn't reach.
+pub(crate) const UNREACHED: u32 = u32::MAX;
+
+/// Returns the hop distance from `src` to every vertex, or [`UNREACHED`].
+pub(crate) fn bfs_distances(graph: &Graph, src: Vertex) -> Vec<u32> {
+    let mut distances = vec![UNREACHED; graph.nvertices()];
+    let mut queue = VecDeque::new();
+    distances[src as usize] = 0;
+    queue.push_back(src);
+    while let Some(v) = queue.pop_front() {
+        let d = distances[v as usize];
+        for &w in graph.neighbors(v) {
+            if distances[w as usize] == UNREACHED {
+                distances[w as usize] = d + 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    distances
+}
+
+/// Largest distance in `distances`, or `None` if any vertex was unreached.
+fn eccentricity(distances: &[u32]) -> Option<u32> {
+    distances.iter().copied().try_fold(0, |acc, d| {
+        if d == UNREACHED {
+            None
+        } else {
+            Some(acc.max(d))
+        }
+    })
+}
+
+/// Returns a lower bound on the diameter from a double-sweep: a BFS from
+/// vertex 0 finds some farthest vertex `u`, and the eccentricity of `u` is
+/// the bound. This is often exact in practice and takes O(n + m).
+///
+/// Returns `None` if the graph is disconnected or empty.
+pub fn diameter(graph: &Graph) -> Option<u32> {
+    if graph.nvertices() == 0 {
+        return None;
+    }
+    let distances = bfs_distances(graph, 0);
+    eccentricity(&distances)?;
+    let (u, _) = distances
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, &d)| d)
+        .unwrap();
+    eccentricity(&bfs_distances(graph, u as Vertex))
+}
+
+/// Returns the exact diameter, the largest eccentricity, by running a BFS
+/// from every vertex in parallel, or `None` if the graph is disconnected or
+/// empty.
+///
+/// Beware that this is O(n * (n + m)).
+pub fn exact_diameter(graph: &Graph) -> Option<u32> {
+    eccentricities(graph)?.into_iter().max()
+}
+
+/// Returns the exact radius, the smallest eccentricity, or `None` if the graph
+/// is disconnected or empty.
+///
+/// Like [`exact_diameter`], this runs a BFS from every vertex and is
+/// O(n * (n + m)).
+pub fn radius(graph: &Graph) -> Option<u32> {
+    eccentricities(graph)?.into_iter().min()
+}
+
+fn eccentricities(graph: &Graph) -> Option<Vec<u32>> {
+    (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| eccentricity(&bfs_distances(graph, v as Vertex)))
+        .collect()
+}
+
+/// Returns a shortest path from `src` to `dst`, including both endpoints, or
+/// `None` if `dst` is unreachable. Runs in O(n + m).
+pub fn shortest_path(graph: &Graph, src: Vertex, dst: Vertex) -> Option<Vec<Vertex>> {
+    let parents = bfs_parents(graph, src, dst)?;
+    let mut path = vec![dst];
+    let mut v = dst;
+    while v != src {
+        v = parents[v as usize];
+        path.push(v);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Returns the number of hops on a shortest path from `src` to `dst`, or
+/// `None` if `dst` is unreachable. Runs in O(n + m).
+pub fn shortest_path_length(graph: &Graph, src: Vertex, dst: Vertex) -> Option<u32> {
+    let parents = bfs_parents(graph, src, dst)?;
+    let mut hops = 0;
+    let mut v = dst;
+    while v != src {
+        v = parents[v as usize];
+        hops += 1;
+    }
+    Some(hops)
+}
+
+/// BFS from `src` which stops once `dst` is reached, returning the BFS tree
+/// parent of every discovered vertex (other vertices have [`UNREACHED`]).
+fn bfs_parents(graph: &Graph, src: Vertex, dst: Vertex) -> Option<Vec<Vertex>> {
+    let mut parents = vec![UNREACHED; graph.nvertices()];
+    let mut queue = VecDeque::new();
+    parents[src as usize] = src;
+    queue.push_back(src);
+    while let Some(v) = queue.pop_front() {
+        if v == dst {
+            return Some(parents);
+        }
+        for &w in graph.neighbors(v) {
+            if parents[w as usize] == UNREACHED {
+                parents[w as usize] = v;
+                queue.push_back(w);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the two sides `[left, right]` of a 2-coloring of the graph, each
+/// sorted, or `None` if the graph has an odd cycle.
+///
+/// Every component is 2-colored by BFS from its smallest vertex, which goes on
+/// the left, in O(n + m) overall.
+pub fn is_bipartite(graph: &Graph) -> Option<[Vec<Vertex>; 2]> {
+    let mut side = vec![UNREACHED; graph.nvertices()];
+    let mut queue = VecDeque::new();
+    for src in 0..graph.nvertices() as Vertex {
+        if side[src as usize] != UNREACHED {
+            continue;
+        }
+        side[src as usize] = 0;
+        queue.push_back(src);
+        while let Some(v) = queue.pop_front() {
+            let s = side[v as usize];
+            for &w in graph.neighbors(v) {
+                if side[w as usize] == UNREACHED {
+                    side[w as usize] = 1 - s;
+                    queue.push_back(w);
+                } else if side[w as usize] == s {
+                    return None;
+                }
+            }
+        }
+    }
+    let (left, right) = (0..graph.nvertices() as Vertex).partition(|&v| side[v as usize] == 0);
+    Some([left, right])
+}
+
+/// Returns the `nsteps + 1` vertices visited by a simple random walk from
+/// `start`, which moves to a uniformly random neighbor at every step, or stays
+/// put if the current vertex is isolated.
+pub fn random_walk(graph: &Graph, start: Vertex, nsteps: usize, seed: u64) -> Vec<Vertex> {
+    let mut rng = seeded_rng(seed);
+    walk(graph, start, nsteps, &mut rng)
+}
+
+/// Returns the empirical visit frequency of every vertex over random walks of
+/// `nsteps` steps, one from each vertex, run in parallel.
+///
+/// On a connected non-bipartite graph this approaches the stationary
+/// distribution `deg(v) / 2m`, which is uniform on regular graphs.
+pub fn stationary_distribution_estimate(graph: &Graph, nsteps: usize, seed: u64) -> Vec<f64> {
+    let n = graph.nvertices();
+    let counts = (0..n)
+        .into_par_iter()
+        .fold(
+            || vec![0u64; n],
+            |mut counts, start| {
+                let mut rng = seeded_rng(thread_seed(seed, start));
+                for v in walk(graph, start as Vertex, nsteps, &mut rng) {
+                    counts[v as usize] += 1;
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![0u64; n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        );
+    let total = (n * (nsteps + 1)) as f64;
+    counts.into_iter().map(|c| c as f64 / total).collect()
+}
+
+/// Returns the PageRank of every vertex, summing to one, treating each
+/// undirected edge as a link in both directions.
+///
+/// Runs power iterations, in parallel over vertices, until the ranks move
+/// less than `tol` in L1 distance or after `max_iter` iterations. Isolated
+/// vertices have no links to follow, so their rank is spread evenly over
+/// all vertices, as if they linked to everything.
+pub fn pagerank(graph: &Graph, damping: f64, tol: f64, max_iter: u32) -> Vec<f64> {
+    let start = Instant::now();
+    let n = graph.nvertices();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut rank = vec![1.0 / n as f64; n];
+    let mut iterations = 0;
+    let mut delta = f64::INFINITY;
+    while iterations < max_iter && delta >= tol {
+        let dangling: f64 = (0..n)
+            .into_par_iter()
+            .filter(|&v| graph.degree(v as Vertex) == 0)
+            .map(|v| rank[v])
+            .sum();
+        let base = (1.0 - damping) / n as f64 + damping * dangling / n as f64;
+        let next: Vec<f64> = (0..n as Vertex)
+            .into_par_iter()
+            .map(|v| {
+                let inflow: f64 = graph
+                    .neighbors(v)
+                    .iter()
+                    .map(|&w| rank[w as usize] / graph.degree(w) as f64)
+                    .sum();
+                base + damping * inflow
+            })
+            .collect();
+        delta = next.par_iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        iterations += 1;
+    }
+    let total: f64 = rank.iter().sum();
+    rank.iter_mut().for_each(|r| *r /= total);
+    println!(
+        "{}",
+        json!({
+            "pagerank_iterations": iterations,
+            "pagerank_delta": delta,
+            "pagerank_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    rank
+}
+
+/// Samples a uniformly random spanning tree of a connected graph with
+/// Wilson's algorithm, or fails if the graph isn't connected.
+///
+/// Starting from a tree holding only vertex 0, a random walk runs from each
+/// vertex not yet in the tree until it hits the tree, remembering only the
+/// last exit taken from every vertex, which erases the walk's loops. The
+/// resulting path then joins the tree.
+pub fn random_spanning_tree(graph: &Graph, seed: u64) -> Result<Graph, GraphError> {
+    if !graph.is_connected() {
+        return Err(GraphError::Disconnected);
+    }
+    let n = graph.nvertices();
+    let mut rng = seeded_rng(seed);
+    let mut in_tree = vec![false; n];
+    let mut next = vec![0 as Vertex; n];
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+    if n > 0 {
+        in_tree[0] = true;
+    }
+    for start in 0..n as Vertex {
+        let mut v = start;
+        while !in_tree[v as usize] {
+            let neighbors = graph.neighbors(v);
+            next[v as usize] = neighbors[rng.gen_range(0..neighbors.len())];
+            v = next[v as usize];
+        }
+        v = start;
+        while !in_tree[v as usize] {
+            in_tree[v as usize] = true;
+            edges.push((v, next[v as usize]));
+            v = next[v as usize];
+        }
+    }
+    Ok(Graph::from_edge_pairs(n, &edges))
+}
+
+fn walk<R: Rng>(graph: &Graph, start: Vertex, nsteps: usize, rng: &mut R) -> Vec<Vertex> {
+    let mut visited = Vec::with_capacity(nsteps + 1);
+    let mut v = start;
+    visited.push(v);
+    for _ in 0..nsteps {
+        let neighbors = graph.neighbors(v);
+        if !neighbors.is_empty() {
+            v = neighbors[rng.gen_range(0..neighbors.len())];
+        }
+        visited.push(v);
+    }
+    visited
+}
+
+/// Returns the Fiedler value, the second-smallest eigenvalue of the
+/// combinatorial Laplacian `L = D - A`, which is zero for disconnected graphs.
+///
+/// For connected graphs this runs inverse iteration orthogonal to the all-ones
+/// kernel of `L`, solving each linear system with conjugate gradients, so no
+/// dense matrices are ever formed.
+pub fn laplacian_spectral_gap(graph: &Graph) -> f64 {
+    fiedler(graph).map(|(value, _)| value).unwrap_or(0.0)
+}
+
+/// Approximates the Cheeger constant `h(G)`, the least ratio
+/// `|∂S| / min(|S|, |V \ S|)` of cut edges to the smaller side of a cut,
+/// which satisfies `λ2 / 2 <= h(G) <= sqrt(2 λ2)` for the Fiedler value `λ2`.
+///
+/// The Fiedler vector is estimated with up to `nsamples` inverse iterations
+/// from a start drawn with `seed`, and the returned ratio is that of the cut
+/// splitting the vertices at its median, so it is an upper bound on `h(G)`.
+/// Disconnected graphs, and those with fewer than two vertices, give zero.
+pub fn cheeger_approx(graph: &Graph, nsamples: usize, seed: u64) -> f64 {
+    let (_, x) = match fiedler_from(graph, nsamples, seed) {
+        Some(fiedler) => fiedler,
+        None => return 0.0,
+    };
+    let n = graph.nvertices();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.par_sort_unstable_by(|&a, &b| x[a].total_cmp(&x[b]).then(a.cmp(&b)));
+    let mut in_low = vec![false; n];
+    order[..n / 2].iter().for_each(|&v| in_low[v] = true);
+    let cut = graph
+        .edges_par()
+        .filter(|&(u, v)| in_low[u as usize] != in_low[v as usize])
+        .count();
+    cut as f64 / (n / 2) as f64
+}
+
+/// Returns the Fiedler value and a corresponding unit eigenvector orthogonal
+/// to the all-ones vector, or `None` if the graph is disconnected or has fewer
+/// than two vertices.
+pub(crate) fn fiedler(graph: &Graph) -> Option<(f64, Vec<f64>)> {
+    fiedler_from(graph, 1000, 0)
+}
+
+/// Like [`fiedler`], but stops after at most `max_iters` inverse iterations,
+/// started from a vector drawn with `seed`.
+fn fiedler_from(graph: &Graph, max_iters: usize, seed: u64) -> Option<(f64, Vec<f64>)> {
+    const TOL: f64 = 1e-10;
+
+    let n = graph.nvertices();
+    if n < 2 || bfs_distances(graph, 0).contains(&UNREACHED) {
+        return None;
+    }
+
+    let mut rng = seeded_rng(seed);
+    let mut x: Vec<f64> = (0..n).map(|_| rng.gen::<f64>() - 0.5).collect();
+    center_and_normalize(&mut x);
+    let mut value = laplacian_quadratic_form(graph, &x);
+    for _ in 0..max_iters {
+        x = laplacian_solve(graph, &x, TOL);
+        center_and_normalize(&mut x);
+        let next = laplacian_quadratic_form(graph, &x);
+        let converged = (next - value).abs() <= TOL * next;
+        value = next;
+        if converged {
+            break;
+        }
+    }
+    Some((value, x))
+}
+
+/// Computes `L x`.
+fn laplacian_apply(graph: &Graph, x: &[f64]) -> Vec<f64> {
+    (0..graph.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let neighbors = graph.neighbors(v as Vertex);
+            neighbors.len() as f64 * x[v] - neighbors.iter().map(|&w| x[w as usize]).sum::<f64>()
+        })
+        .collect()
+}
+
+/// Computes `x^T L x`, the sum of `(x_u - x_v)^2` over edges.
+fn laplacian_quadratic_form(graph: &Graph, x: &[f64]) -> f64 {
+    graph
+        .edges_par()
+        .map(|(u, v)| (x[u as usize] - x[v as usize]).powi(2))
+        .sum()
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    x.par_iter().zip(y).map(|(a, b)| a * b).sum()
+}
+
+/// Projects out the all-ones component of `x` and scales it to unit length.
+fn center_and_normalize(x: &mut [f64]) {
+    let mean = x.par_iter().sum::<f64>() / x.len() as f64;
+    x.par_iter_mut().for_each(|a| *a -= mean);
+    let norm = dot(x, x).sqrt();
+    x.par_iter_mut().for_each(|a| *a /= norm);
+}
+
+/// Solves `L y = b` for `b` orthogonal to the all-ones vector with conjugate
+/// gradients, to relative residual `tol`, on a connected graph.
+fn laplacian_solve(graph: &Graph, b: &[f64], tol: f64) -> Vec<f64> {
+    let n = b.len();
+    let mut y = vec![0.0; n];
+    let mut r = b.to_vec();
+    let mut p = r.clone();
+    let mut rr = dot(&r, &r);
+    let threshold = tol * tol * rr;
+    for _ in 0..(10 * n) {
+        if rr <= threshold {
+            break;
+        }
+        let lp = laplacian_apply(graph, &p);
+        let alpha = rr / dot(&p, &lp);
+        y.par_iter_mut().zip(&p).for_each(|(a, b)| *a += alpha * b);
+        r.par_iter_mut().zip(&lp).for_each(|(a, b)| *a -= alpha * b);
+        let next_rr = dot(&r, &r);
+        let beta = next_rr / rr;
+        p.par_iter_mut()
+            .zip(&r)
+            .for_each(|(a, b)| *a = b + beta * *a);
+        rr = next_rr;
+    }
+    y
+}
+
+/// Returns the graph over the shared vertex set of `a` and `b` whose edges are
+/// in either input.
+pub fn union(a: &Graph, b: &Graph) -> Result<Graph, GraphError> {
+    merge_with(a, b, |x, y, out| {
+        let (mut i, mut j) = (0, 0);
+        while i < x.len() && j < y.len() {
+            match x[i].cmp(&y[j]) {
+                Ordering::Less => {
+                    out.push(x[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    out.push(y[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    out.push(x[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        out.extend_from_slice(&x[i..]);
+        out.extend_from_slice(&y[j..]);
+    })
+}
+
+/// Returns the graph over the shared vertex set of `a` and `b` whose edges are
+/// in both inputs.
+pub fn intersection(a: &Graph, b: &Graph) -> Result<Graph, GraphError> {
+    merge_with(a, b, |x, y, out| {
+        let (mut i, mut j) = (0, 0);
+        while i < x.len() && j < y.len() {
+            match x[i].cmp(&y[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    out.push(x[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+    })
+}
+
+/// Builds a graph by merging the sorted neighbor lists of each vertex in `a`
+/// and `b` into a new sorted list, in parallel over vertices.
+fn merge_with<F>(a: &Graph, b: &Graph, merge: F) -> Result<Graph, GraphError>
+where
+    F: Fn(&[Vertex], &[Vertex], &mut Vec<Vertex>) + Sync,
+{
+    if a.nvertices() != b.nvertices() {
+        return Err(GraphError::VertexCountMismatch {
+            left: a.nvertices(),
+            right: b.nvertices(),
+        });
+    }
+    let lists = (0..a.nvertices())
+        .into_par_iter()
+        .map(|v| {
+            let v = v as Vertex;
+            let mut out = Vec::new();
+            merge(a.neighbors(v), b.neighbors(v), &mut out);
+            out
+        })
+        .collect();
+    Ok(Graph::from_lists(lists))
+}
+
+/// Returns the edges of a minimum spanning forest of `graph`, which is a
+/// spanning tree if the graph is connected.
+///
+/// Uses Borůvka's algorithm: each round finds the lightest edge leaving
+/// every component, in parallel over vertices, and merges the components
+/// along those edges. Ties are broken by endpoint ids, so the chosen edges
+/// never form a cycle. The number of components at least halves each
+/// round, for O(m log n) work overall.
+pub fn minimum_spanning_tree(graph: &WeightedGraph) -> Graph {
+    let start = Instant::now();
+    let n = graph.as_unweighted().nvertices();
+    let mut parent: Vec<Vertex> = (0..n as Vertex).collect();
+    let mut component: Vec<Vertex> = parent.clone();
+    let mut tree = Vec::with_capacity(n.saturating_sub(1));
+    let mut rounds = 0;
+    let lighter = |a: &(f32, Vertex, Vertex), b: &(f32, Vertex, Vertex)| {
+        a.0.total_cmp(&b.0).then((a.1, a.2).cmp(&(b.1, b.2)))
+    };
+    loop {
+        rounds += 1;
+        let lightest: Vec<Option<(f32, Vertex, Vertex)>> = (0..n as Vertex)
+            .into_par_iter()
+            .map(|v| {
+                graph
+                    .as_unweighted()
+                    .neighbors(v)
+                    .iter()
+                    .zip(graph.neighbor_weights(v))
+                    .filter(|(&w, _)| component[w as usize] != component[v as usize])
+                    .map(|(&w, &weight)| (weight, v.min(w), v.max(w)))
+                    .min_by(lighter)
+            })
+            .collect();
+
+        let mut best: Vec<Option<(f32, Vertex, Vertex)>> = vec![None; n];
+        for (v, edge) in lightest.into_iter().enumerate() {
+            let edge = match edge {
+                Some(edge) => edge,
+                None => continue,
+            };
+            let slot = &mut best[component[v] as usize];
+            if slot.is_none_or(|b| lighter(&edge, &b) == Ordering::Less) {
+                *slot = Some(edge);
+            }
+        }
+
+        let mut merged = false;
+        for (_, u, v) in best.into_iter().flatten() {
+            let (ru, rv) = (find_root(&mut parent, u), find_root(&mut parent, v));
+            if ru != rv {
+                parent[ru.max(rv) as usize] = ru.min(rv);
+                tree.push((u, v));
+                merged = true;
+            }
+        }
+        if !merged {
+            break;
+        }
+        for v in 0..n as Vertex {
+            component[v as usize] = find_root(&mut parent, v);
+        }
+    }
+
+    println!(
+        "{}",
+        json!({
+            "mst_edges": tree.len(),
+            "mst_rounds": rounds,
+            "mst_time": format!("{:.0?}", Instant::now().duration_since(start)),
+        })
+    );
+    Graph::from_edge_pairs(n, &tree)
+}
+
+/// Returns the root of `v` in the union-find forest `parent`, halving the
+/// path along the way.
+fn find_root(parent: &mut [Vertex], mut v: Vertex) -> Vertex {
+    while parent[v as usize] != v {
+        let grandparent = parent[parent[v as usize] as usize];
+        parent[v as usize] = grandparent;
+        v = grandparent;
+    }
+    v
+}
+
+/// Converts an undirected petgraph graph, numbering vertices by node index.
+/// Self loops are dropped and parallel edges merged.
+#[cfg(feature = "petgraph")]
+pub fn from_petgraph<N, E>(g: &petgraph::Graph<N, E, petgraph::Undirected>) -> Graph {
+    use petgraph::visit::EdgeRef;
+    let edges: Vec<(Vertex, Vertex)> = g
+        .edge_references()
+        .map(|e| (e.source().index() as Vertex, e.target().index() as Vertex))
+        .collect();
+    Graph::from_edge_pairs(g.node_count(), &edges)
+}
+
+/// Converts to a petgraph graph whose node `v` is weighted by `v` itself.
+#[cfg(feature = "petgraph")]
+pub fn to_petgraph(g: &Graph) -> petgraph::Graph<u32, (), petgraph::Undirected> {
+    let mut out = petgraph::Graph::with_capacity(g.nvertices(), g.nedges());
+    let nodes: Vec<_> = (0..g.nvertices() as Vertex)
+        .map(|v| out.add_node(v))
+        .collect();
+    for (u, v) in g.edges() {
+        out.add_edge(nodes[u as usize], nodes[v as usize], ());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn petersen() -> Graph {
+        let edges: Vec<_> = (0..5)
+            .flat_map(|i| vec![(i, (i + 1) % 5), (i, i + 5), (i + 5, (i + 2) % 5 + 5)])
+            .collect();
+        Graph::from_edge_pairs(10, &edges)
+    }
+
+    fn cycle(n: u32) -> Graph {
+        let edges: Vec<_> = (0..n).map(|v| (v, (v + 1) % n)).collect();
+        Graph::from_edge_pairs(n as usize, &edges)
+    }
+
+    /// Cliques on `[0, k)` and `[k + 1, 2k + 1)`, both adjacent to vertex `k`
+    /// through a single edge each.
+    fn bridged_cliques(k: u32) -> Graph {
+        let mut edges = Vec::new();
+        for offset in [0, k + 1] {
+            for u in 0..k {
+                edges.extend((u + 1..k).map(|v| (offset + u, offset + v)));
+            }
+        }
+        edges.push((k - 1, k));
+        edges.push((k, k + 1));
+        Graph::from_edge_pairs(2 * k as usize + 1, &edges)
+    }
+
+    fn path(n: u32) -> Graph {
+        let edges: Vec<_> = (1..n).map(|v| (v - 1, v)).collect();
+        Graph::from_edge_pairs(n as usize, &edges)
+    }
+
+    /// Disjoint cliques on `[0, a)` and `[a, a + b)`.
+    fn disjoint_cliques(a: u32, b: u32) -> Graph {
+        let mut edges = Vec::new();
+        for (lo, hi) in [(0, a), (a, a + b)] {
+            for u in lo..hi {
+                edges.extend((u + 1..hi).map(|v| (u, v)));
+            }
+        }
+        Graph::from_edge_pairs((a + b) as usize, &edges)
+    }
+
+    fn complete_bipartite(a: u32, b: u32) -> Graph {
+        let edges: Vec<_> = (0..a)
+            .flat_map(|u| (a..a + b).map(move |v| (u, v)))
+            .collect();
+        Graph::from_edge_pairs((a + b) as usize, &edges)
+    }
+
+    fn weighted(n: usize, edges: &[(Vertex, Vertex, f32)]) -> WeightedGraph {
+        let mut lists = vec![Vec::new(); n];
+        for &(u, v, w) in edges {
+            lists[u as usize].push((v, w));
+            lists[v as usize].push((u, w));
+        }
+        let mut offsets = vec![0];
+        let mut neighbors = Vec::new();
+        let mut weights = Vec::new();
+        for mut list in lists {
+            list.sort_by_key(|&(v, _)| v);
+            neighbors.extend(list.iter().map(|&(v, _)| v));
+            weights.extend(list.iter().map(|&(_, w)| w));
+            offsets.push(neighbors.len());
+        }
+        WeightedGraph::new(offsets, neighbors, weights)
+    }
+
+    fn star(leaves: u32) -> Graph {
+        let edges: Vec<_> = (1..=leaves).map(|v| (0, v)).collect();
+        Graph::from_edge_pairs(leaves as usize + 1, &edges)
+    }
+
+    #[test]
+    fn clustering_of_complete_and_tree() {
+        let k5 = Graph::complete(5);
+        assert_eq!(clustering_coefficient(&k5), 1.0);
+        assert_eq!(average_clustering_coefficient(&k5), 1.0);
+        assert_eq!(transitivity(&k5), 1.0);
+        let tree = Graph::from_edge_pairs(6, &[(0, 1), (0, 2), (1, 3), (1, 4), (2, 5)]);
+        assert_eq!(clustering_coefficient(&tree), 0.0);
+        assert_eq!(average_clustering_coefficient(&tree), 0.0);
+        assert_eq!(transitivity(&tree), 0.0);
+    }
+
+    #[test]
+    fn degree_histogram_sums() {
+        let g = crate::generators::watts_strogatz(100, 6, 0.3, 1).unwrap();
+        let histogram = g.degree_histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), g.nvertices());
+        let degree_sum: usize = histogram.iter().enumerate().map(|(d, &c)| d * c).sum();
+        assert_eq!(degree_sum, 2 * g.nedges());
+        assert_eq!(g.max_degree(), histogram.len() - 1);
+    }
+
+    #[test]
+    fn triangles_and_motifs() {
+        assert_eq!(count_triangles(&Graph::complete(4)), 4);
+        assert_eq!(count_triangles(&petersen()), 0);
+        let k4 = motif_counts(&Graph::complete(4));
+        assert_eq!((k4.triangles, k4.four_cycles), (4, 3));
+        let c4 = motif_counts(&cycle(4));
+        assert_eq!((c4.triangles, c4.four_cycles), (0, 1));
+        assert_eq!(c4.paths3, 4);
+    }
+
+    #[test]
+    fn cut_vertices_and_bridges() {
+        let g = bridged_cliques(4);
+        assert_eq!(articulation_points(&g), vec![3, 4, 5]);
+        assert_eq!(bridges(&g), vec![(3, 4), (4, 5)]);
+        assert!(articulation_points(&cycle(6)).is_empty());
+        assert!(bridges(&cycle(6)).is_empty());
+    }
+
+    #[test]
+    fn core_numbers_of_pendant_clique() {
+        // K_4 on [0, 4) with a path 3 - 4 - 5 hanging off of it
+        let mut edges = vec![(3, 4), (4, 5)];
+        for u in 0..4 {
+            edges.extend((u + 1..4).map(|v| (u, v)));
+        }
+        let g = Graph::from_edge_pairs(6, &edges);
+        let cores = kcore_decomposition(&g);
+        assert_eq!(cores, vec![3, 3, 3, 3, 1, 1]);
+        // the 3-core is a K_4, which needs 4 colors
+        let max_core = *cores.iter().max().unwrap();
+        let limit = Duration::from_secs(1);
+        assert!(crate::color::is_k_colorable(&g, max_core, limit).is_none());
+        assert!(crate::color::greedy_smallest_last(&g).0 <= max_core + 1);
+    }
+
+    #[test]
+    fn diameter_of_paths_and_cycles() {
+        for n in [2, 7, 10] {
+            assert_eq!(diameter(&path(n)), Some(n - 1));
+            assert_eq!(exact_diameter(&path(n)), Some(n - 1));
+            assert_eq!(radius(&path(n)), Some(n / 2));
+        }
+        for n in [5, 8, 11] {
+            assert_eq!(diameter(&cycle(n)), Some(n / 2));
+            assert_eq!(exact_diameter(&cycle(n)), Some(n / 2));
+            assert_eq!(radius(&cycle(n)), Some(n / 2));
+        }
+        assert_eq!(diameter(&disjoint_cliques(3, 3)), None);
+    }
+
+    #[test]
+    fn shortest_paths() {
+        // a square 0 - 1 - 2 - 3 - 0 with a tail 1 - 4 - 5
+        let g = Graph::from_edge_pairs(6, &[(0, 1), (1, 2), (2, 3), (3, 0), (1, 4), (4, 5)]);
+        assert_eq!(shortest_path(&g, 0, 5), Some(vec![0, 1, 4, 5]));
+        assert_eq!(shortest_path(&g, 5, 2), Some(vec![5, 4, 1, 2]));
+        assert_eq!(shortest_path_length(&g, 3, 5), Some(4));
+        assert_eq!(shortest_path(&g, 2, 2), Some(vec![2]));
+        let disconnected = disjoint_cliques(3, 2);
+        assert_eq!(shortest_path(&disconnected, 0, 4), None);
+        assert_eq!(shortest_path_length(&disconnected, 0, 4), None);
+    }
+
+    #[test]
+    fn edges_match_nedges() {
+        let g = petersen();
+        assert_eq!(g.edges().count(), g.nedges());
+        assert_eq!(g.edges_par().count(), g.nedges());
+        assert!(g.edges().all(|(u, v)| u < v));
+    }
+
+    #[test]
+    fn path_fiedler_value() {
+        for n in [5u32, 10, 20] {
+            let expected = 2.0 - 2.0 * (std::f64::consts::PI / n as f64).cos();
+            let value = laplacian_spectral_gap(&path(n));
+            assert!((value - expected).abs() < 1e-6, "{} vs {}", value, expected);
+        }
+        assert_eq!(laplacian_spectral_gap(&disjoint_cliques(3, 3)), 0.0);
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let left = Graph::from_edge_pairs(4, &[(0, 1), (1, 2)]);
+        let right = Graph::from_edge_pairs(4, &[(1, 2), (2, 3)]);
+        assert_eq!(union(&left, &right).unwrap(), path(4));
+        assert_eq!(
+            intersection(&left, &right).unwrap(),
+            Graph::from_edge_pairs(4, &[(1, 2)])
+        );
+        assert_eq!(intersection(&left, &left).unwrap(), left);
+        assert!(union(&left, &path(3)).is_err());
+    }
+
+    #[test]
+    fn complement_of_path_and_complete() {
+        let n = 6;
+        let complement = path(n).complement();
+        for u in 0..n {
+            for v in 0..n {
+                let on_path = u + 1 == v || v + 1 == u;
+                let adjacent = complement.neighbors(u).contains(&v);
+                assert_eq!(adjacent, u != v && !on_path);
+            }
+        }
+        assert_eq!(
+            Graph::complete(n).complement(),
+            Graph::from_edge_pairs(n as usize, &[])
+        );
+    }
+
+    #[test]
+    fn bipartite_cycles() {
+        let [left, right] = is_bipartite(&cycle(6)).unwrap();
+        assert_eq!(left, vec![0, 2, 4]);
+        assert_eq!(right, vec![1, 3, 5]);
+        assert!(is_bipartite(&cycle(7)).is_none());
+        assert!(is_bipartite(&petersen()).is_none());
+    }
+
+    #[test]
+    fn clique_bound_on_dense_graphs() {
+        let limit = Duration::from_secs(1);
+        assert_eq!(clique_lower_bound(&Graph::complete(6), limit), 6);
+        // K_5 on [0, 5) with a few sparse extra vertices
+        let mut edges = vec![(4, 5), (5, 6), (6, 7), (7, 5)];
+        for u in 0..5 {
+            edges.extend((u + 1..5).map(|v| (u, v)));
+        }
+        assert_eq!(
+            clique_lower_bound(&Graph::from_edge_pairs(8, &edges), limit),
+            5
+        );
+        assert_eq!(clique_lower_bound(&complete_bipartite(3, 4), limit), 2);
+        // the complement of C_7 has clique number 3
+        assert_eq!(clique_lower_bound(&cycle(7).complement(), limit), 3);
+    }
+
+    #[test]
+    fn regular_graph_walks_uniformly() {
+        let g = crate::generators::torus_2d(5, 5);
+        let estimate = stationary_distribution_estimate(&g, 20_000, 11);
+        assert!((estimate.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        let uniform = 1.0 / g.nvertices() as f64;
+        for p in estimate {
+            assert!((p - uniform).abs() < 0.1 * uniform, "{} vs {}", p, uniform);
+        }
+        let walk = random_walk(&g, 0, 100, 11);
+        assert_eq!(walk.len(), 101);
+        assert!(walk.windows(2).all(|w| g.neighbors(w[0]).contains(&w[1])));
+    }
+
+    #[test]
+    fn connectivity_of_disjoint_cliques() {
+        let g = disjoint_cliques(4, 3);
+        assert!(!g.is_connected());
+        assert_eq!(g.largest_connected_component(), Graph::complete(4));
+        assert!(path(5).is_connected());
+    }
+
+    #[test]
+    fn vertices_by_degree_iter_starts_at_max() {
+        let g = crate::generators::watts_strogatz(200, 4, 0.5, 3).unwrap();
+        let min_degree = (0..g.nvertices() as Vertex).map(|v| g.degree(v)).min();
+        let first = g.vertices_by_degree_iter(true).next().unwrap();
+        assert_eq!(g.degree(first), g.max_degree());
+        let last = g.vertices_by_degree_iter(false).next().unwrap();
+        assert_eq!(Some(g.degree(last)), min_degree);
+        for descending in [true, false] {
+            let lazy: Vec<_> = g.vertices_by_degree_iter(descending).collect();
+            assert_eq!(lazy, g.vertices_by_degree(descending));
+        }
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn petgraph_roundtrip() {
+        let g = crate::generators::random_regular(50, 4, 7).unwrap();
+        let pg = to_petgraph(&g);
+        assert_eq!(pg.node_count(), g.nvertices());
+        assert_eq!(pg.edge_count(), g.nedges());
+        assert_eq!(from_petgraph(&pg), g);
+    }
+
+    #[test]
+    fn two_hop_neighborhoods() {
+        assert_eq!(path(5).two_hop_neighborhood(2), vec![0, 1, 3, 4]);
+        assert_eq!(path(5).two_hop_neighborhood(0), vec![1, 2]);
+        let g = crate::generators::watts_strogatz(60, 4, 0.5, 5).unwrap();
+        for v in 0..g.nvertices() as Vertex {
+            let hood = g.two_hop_neighborhood(v);
+            assert!(hood.windows(2).all(|w| w[0] < w[1]));
+            assert!(!hood.contains(&v));
+        }
+    }
+
+    #[test]
+    fn dedup_and_remove_self_loops() {
+        // 0: [0, 1, 1, 2], 1: [0, 0, 1], 2: [0, 2, 2]
+        let offsets = vec![0, 4, 7, 10];
+        let neighbors = vec![0, 1, 1, 2, 0, 0, 1, 0, 2, 2];
+        let (offsets, neighbors) = Graph::dedup_edges(offsets, neighbors);
+        assert_eq!(offsets, vec![0, 3, 5, 7]);
+        assert_eq!(neighbors, vec![0, 1, 2, 0, 1, 0, 2]);
+        let (offsets, neighbors) = Graph::remove_self_loops(offsets, neighbors);
+        assert_eq!(offsets, vec![0, 2, 3, 4]);
+        assert_eq!(neighbors, vec![1, 2, 0, 0]);
+        assert_eq!(
+            Graph::new(offsets, neighbors),
+            Graph::from_edge_pairs(3, &[(0, 1), (0, 2)])
+        );
+    }
+
+    #[test]
+    fn max_and_min_degree() {
+        let k5 = Graph::complete(5);
+        assert_eq!((k5.min_degree(), k5.max_degree()), (4, 4));
+        assert_eq!(k5.avg_degree(), 4.0);
+        let p = path(6);
+        assert_eq!((p.min_degree(), p.max_degree()), (1, 2));
+        assert!((p.avg_degree() - 10.0 / 6.0).abs() < 1e-12);
+        let empty = Graph::from_edge_pairs(4, &[]);
+        assert_eq!((empty.min_degree(), empty.max_degree()), (0, 0));
+        let none = Graph::from_edge_pairs(0, &[]);
+        assert_eq!((none.min_degree(), none.max_degree()), (0, 0));
+    }
+
+    #[test]
+    fn neighborhood_sizes() {
+        let g = Graph::from_edge_pairs(8, &[(0, 1), (1, 2), (2, 3), (1, 4), (5, 6), (6, 7)]);
+        let components = connected_components(&g);
+        for v in 0..8 {
+            assert_eq!(g.neighborhood_size(v, 1), g.degree(v));
+            let component_size = components
+                .iter()
+                .filter(|&&c| c == components[v as usize])
+                .count();
+            let component: Vec<Vertex> = (0..8)
+                .filter(|&w| components[w as usize] == components[v as usize])
+                .collect();
+            let d = exact_diameter(&g.induced_subgraph(&component)).unwrap();
+            assert_eq!(g.neighborhood_size(v, d), component_size - 1);
+        }
+    }
+
+    #[test]
+    fn has_edge_and_common_neighbors() {
+        let k4 = Graph::complete(4);
+        for u in 0..4 {
+            for v in 0..4 {
+                assert_eq!(k4.has_edge(u, v), u != v);
+                if u != v {
+                    assert_eq!(k4.common_neighbor_count(u, v), 2);
+                }
+            }
+        }
+        let p = path(5);
+        assert!(p.has_edge(1, 2) && p.has_edge(2, 1));
+        assert!(!p.has_edge(0, 2) && !p.has_edge(0, 4));
+        assert_eq!(p.common_neighbor_count(0, 2), 1);
+        assert_eq!(p.common_neighbor_count(0, 1), 0);
+        assert_eq!(p.common_neighbor_count(0, 4), 0);
+    }
+
+    #[test]
+    fn mst_of_weighted_grid() {
+        // horizontal edges and the first column are light, the rest heavy
+        let (rows, cols) = (3, 4);
+        let id = |r: u32, c: u32| r * cols + c;
+        let mut edges = Vec::new();
+        let mut expected = Vec::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                if c + 1 < cols {
+                    edges.push((id(r, c), id(r, c + 1), 1.0));
+                    expected.push((id(r, c), id(r, c + 1)));
+                }
+                if r + 1 < rows {
+                    let weight = if c == 0 { 1.0 } else { 5.0 };
+                    edges.push((id(r, c), id(r + 1, c), weight));
+                    if c == 0 {
+                        expected.push((id(r, c), id(r + 1, c)));
+                    }
+                }
+            }
+        }
+        let n = (rows * cols) as usize;
+        let tree = minimum_spanning_tree(&weighted(n, &edges));
+        assert_eq!(tree.nedges(), n - 1);
+        assert!(tree.is_connected());
+        assert_eq!(tree, Graph::from_edge_pairs(n, &expected));
+    }
+
+    #[test]
+    fn ego_network_of_star_hub() {
+        let g = star(6);
+        assert_eq!(g.ego_network(0, 1), g);
+        assert_eq!(g.ego_network(1, 1), path(2));
+    }
+
+    #[test]
+    fn generations_of_path_and_bipartite() {
+        let layers = topological_generations(&path(5), &[0]);
+        assert_eq!(layers, (0..5).map(|v| vec![v]).collect::<Vec<_>>());
+        let k33 = complete_bipartite(3, 3);
+        assert_eq!(
+            topological_generations(&k33, &[0, 1, 2]),
+            vec![vec![0, 1, 2], vec![3, 4, 5]]
+        );
+        for source in 0..6 {
+            let layers = topological_generations(&k33, &[source]);
+            assert_eq!(layers.len(), 3);
+            assert_eq!(layers[0], vec![source]);
+            assert_eq!(layers[1].len(), 3);
+        }
+    }
+
+    #[test]
+    fn powers() {
+        let g = petersen();
+        assert_eq!(g.power(1), g);
+        // the petersen graph has diameter 2
+        assert_eq!(g.power(2), Graph::complete(10));
+        for k in 1..4 {
+            assert_eq!(Graph::complete(5).power(k), Graph::complete(5));
+        }
+    }
+
+    #[test]
+    fn line_graphs() {
+        let g = petersen();
+        let (line, edges) = g.line_graph();
+        assert_eq!(line.nvertices(), g.nedges());
+        assert_eq!(edges, g.edges().collect::<Vec<_>>());
+        assert_eq!(path(6).line_graph().0, path(5));
+    }
+
+    #[test]
+    fn contract_clique() {
+        let contracted = Graph::complete(5).contract(&[0; 5]);
+        assert_eq!(contracted.nvertices(), 1);
+        assert_eq!(contracted.nedges(), 0);
+        let g = path(4).contract(&[0, 0, 1, 1]);
+        assert_eq!(g, path(2));
+    }
+
+    #[test]
+    fn pagerank_of_cycle_and_dangling() {
+        let rank = pagerank(&cycle(7), 0.85, 1e-12, 100);
+        for r in rank {
+            assert!((r - 1.0 / 7.0).abs() < 1e-9);
+        }
+        // a triangle and an isolated vertex, whose rank is spread evenly, so
+        // it converges to (1 - d) / (n - d)
+        let g = Graph::from_edge_pairs(4, &[(0, 1), (1, 2), (2, 0)]);
+        let rank = pagerank(&g, 0.85, 1e-12, 1000);
+        assert!((rank.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((rank[3] - 0.15 / 3.15).abs() < 1e-9, "{}", rank[3]);
+        assert!((rank[0] - rank[1]).abs() < 1e-9 && rank[0] > rank[3]);
+    }
+
+    #[test]
+    fn random_spanning_tree_spans() {
+        let g = crate::generators::watts_strogatz(100, 4, 0.2, 8).unwrap();
+        let tree = random_spanning_tree(&g, 9).unwrap();
+        assert_eq!(tree.nvertices(), g.nvertices());
+        assert_eq!(tree.nedges(), g.nvertices() - 1);
+        assert!(tree.is_connected());
+        assert!(tree.edges().all(|(u, v)| g.has_edge(u, v)));
+        assert!(random_spanning_tree(&disjoint_cliques(3, 3), 9).is_err());
+    }
+
+    #[test]
+    fn independent_sets() {
+        let is_independent =
+            |g: &Graph, set: &[Vertex]| set.iter().all(|&u| set.iter().all(|&v| !g.has_edge(u, v)));
+        let grid = crate::generators::grid_2d(6, 7);
+        // every forest is bipartite, and the minimum degree vertex is always a
+        // leaf, which some maximum independent set contains
+        for seed in 0..4 {
+            let tree = random_spanning_tree(&grid, seed).unwrap();
+            let set = greedy_independent_set(&tree, seed);
+            assert!(is_independent(&tree, &set));
+            assert!(set.len() * 2 >= tree.nvertices());
+            assert!(is_independent(&grid, &greedy_independent_set(&grid, seed)));
+        }
+        let set = greedy_independent_set(&cycle(10), 4);
+        assert!(is_independent(&cycle(10), &set));
+        assert_eq!(set.len(), 5);
+        let set = greedy_independent_set(&complete_bipartite(3, 5), 4);
+        assert_eq!(set, vec![3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn embedded_clique_is_high_core() {
+        // K_5 on [0, 5) attached to a long path
+        let mut edges: Vec<_> = (5..20).map(|v| (v - 1, v)).collect();
+        for u in 0..5 {
+            edges.extend((u + 1..5).map(|v| (u, v)));
+        }
+        let g = Graph::from_edge_pairs(20, &edges);
+        let cores = core_numbers(&g);
+        assert!(cores[..5].iter().all(|&c| c == 4));
+        assert!(cores[5..].iter().all(|&c| c == 1));
+        assert_eq!(high_core_subgraph(&g, 4), Graph::complete(5));
+    }
+
+    #[test]
+    fn cheeger_of_dumbbell_and_expander() {
+        let mut edges = vec![(9, 10)];
+        for offset in [0, 10] {
+            for u in 0..10 {
+                edges.extend((u + 1..10).map(|v| (offset + u, offset + v)));
+            }
+        }
+        let dumbbell = Graph::from_edge_pairs(20, &edges);
+        let h = cheeger_approx(&dumbbell, 100, 1);
+        assert!(h <= 0.11, "dumbbell h = {}", h);
+        let expander = crate::generators::random_regular(100, 6, 2).unwrap();
+        let h = cheeger_approx(&expander, 100, 1);
+        assert!(h >= 0.5, "expander h = {}", h);
+    }
+
+    #[test]
+    fn jaccard_of_twins() {
+        // 0 and 1 are both adjacent to exactly 2 and 3
+        let g = Graph::from_edge_pairs(5, &[(0, 2), (0, 3), (1, 2), (1, 3), (3, 4)]);
+        assert_eq!(jaccard_similarity(&g, 0, 1), 1.0);
+        assert_eq!(common_neighbors(&g, 0, 1), 2);
+        assert_eq!(jaccard_similarity(&g, 2, 3), 2.0 / 3.0);
+        assert_eq!(jaccard_similarity(&g, 2, 4), 0.0);
+        let per_edge = all_pairs_jaccard_for_edges(&g);
+        assert_eq!(per_edge.len(), g.nedges());
+    }
 }
@@ -4,33 +4,428 @@
 
 
 
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::iter;
+use std::path::{Path, PathBuf};
 
 use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::time::Instant;
 
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use bstr::ByteSlice;
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
+};
+use rayon::slice::ParallelSlice;
 use serde_json::json;
 
-use crate::{graph::Graph, simsvm, Scanner};
+use crate::{
+    graph::{Graph, LabeledGraph, WeightedGraph},
+    simsvm::{self, SimSvmLineIter},
+    DelimIter, Scanner,
+};
 
-/// Reads a single file behind a scanner into an in-memory graph.
+/// Errors from reading graph files.
+#[derive(Debug)]
+pub enum GraphIoError {
+    Io(io::Error),
+    /// A malformed word at the 1-indexed `line` and byte `column` of `file`.
+    ParseError {
+        file: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for GraphIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::ParseError {
+                file,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", file.display(), line, column, message),
+        }
+    }
+}
+
+impl Error for GraphIoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::ParseError { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for GraphIoError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Cleanup applied to edges by [`read_with_options`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Drop edges repeated in the input, including edges listed from both
+    /// of their endpoints.
+    pub deduplicate: bool,
+    /// Drop edges from a vertex to itself.
+    pub remove_self_loops: bool,
+}
+
+/// Both cleanups are on by default, since [`Graph`] requires them. Turning
+/// one off skips its pass, which is only safe for input known to be clean.
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            deduplicate: true,
+            remove_self_loops: true,
+        }
+    }
+}
+
+/// Reads a single file behind a scanner into an in-memory graph, panicking
+/// on malformed input. This is [`read_with_nvertices`] with one more than the
+/// largest vertex in the file, found by the same validating pass as
+/// [`try_read`].
 pub fn read(scanner: &Scanner) -> Graph {
-    let nvertices = 1 + scanner
+    let nvertices = try_count_vertices(scanner).unwrap_or_else(|e| panic!("{}", e));
+    read_with_nvertices(scanner, nvertices)
+}
+
+/// Like [`read`], with control over the edge cleanup passes.
+pub fn read_with_options(scanner: &Scanner, options: ReadOptions) -> Graph {
+    try_read_with_options(scanner, options).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Reads a single file behind a scanner into an in-memory graph, after a
+/// first pass checking that every line is a non-empty list of `u32`s.
+pub fn try_read(scanner: &Scanner) -> Result<Graph, GraphIoError> {
+    try_read_with_options(scanner, ReadOptions::default())
+}
+
+fn try_read_with_options(scanner: &Scanner, options: ReadOptions) -> Result<Graph, GraphIoError> {
+    let nvertices = try_count_vertices(scanner)?;
+    Ok(read_adjacency(scanner, nvertices, options))
+}
+
+/// One more than the largest vertex in the adjacency lists behind the
+/// scanner, or one if there are none, checking every line on the way.
+fn try_count_vertices(scanner: &Scanner) -> Result<usize, GraphIoError> {
+    let lenient = scanner.is_lenient();
+    let maxes = scanner
+        .try_fold(
+            |_| None,
+            |max, line, path, lineno| match check_adjacency(max, line, path, lineno) {
+                // counted when reading the lines for real
+                Err(GraphIoError::ParseError { .. }) if lenient => Ok(max),
+                result => result,
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(maxes
+        .into_iter()
+        .flatten()
+        .max()
+        .map_or(1, |m| m as usize + 1))
+}
+
+/// Estimates the number of vertices in files readable by [`read`] as one
+/// more than the largest vertex starting a line, which is much cheaper than
+/// the full validation pass of [`try_read`]. This is exact for files where
+/// every vertex gets a line, such as those from [`write`], but otherwise may
+/// miss vertices only listed as neighbors.
+pub fn estimate_nvertices(scanner: &Scanner) -> usize {
+    scanner.max_leading_u32().map_or(1, |m| m as usize + 1)
+}
+
+/// Folds the largest vertex in a line into `max`, failing on malformed words.
+fn check_adjacency(
+    max: Option<u32>,
+    line: DelimIter<'_>,
+    path: &Path,
+    lineno: usize,
+) -> Result<Option<u32>, GraphIoError> {
+    let error = |column: usize, message: String| GraphIoError::ParseError {
+        file: path.to_owned(),
+        line: lineno + 1,
+        column: column + 1,
+        message,
+    };
+    let mut max = max;
+    let mut column = 0;
+    let mut empty = true;
+    for word in line {
+        let vertex = std::str::from_utf8(word)
+            .ok()
+            .and_then(|w| w.parse::<u32>().ok())
+            .ok_or_else(|| error(column, format!("bad vertex `{}`", word.to_str_lossy())))?;
+        max = Some(max.map_or(vertex, |m| m.max(vertex)));
+        column += word.len() + 1;
+        empty = false;
+    }
+    if empty {
+        return Err(error(0, "missing target vertex".to_owned()));
+    }
+    Ok(max)
+}
+
+/// Like [`read`], but over the vertices `[0, nvertices)`, which may include
+/// isolated vertices that never appear in the file. Panics if the file
+/// mentions a vertex outside that range.
+pub fn read_with_nvertices(scanner: &Scanner, nvertices: usize) -> Graph {
+    read_adjacency(scanner, nvertices, ReadOptions::default())
+}
+
+/// Reads adjacency lists over the vertices `[0, nvertices)`, which every
+/// adjacency list reader above ends up calling.
+fn read_adjacency(scanner: &Scanner, nvertices: usize, options: ReadOptions) -> Graph {
+    read_lines(scanner, parse_adjacency, nvertices, options)
+}
+
+/// Reads an edge list, with one `<u> <v>` edge per line, into an in-memory
+/// graph. Empty lines and lines starting with `#` or `%` are skipped.
+pub fn read_edgelist(scanner: &Scanner) -> Graph {
+    let nvertices = count_vertices(scanner, parse_edge);
+    read_lines(scanner, parse_edge, nvertices, ReadOptions::default())
+}
+
+/// Reads an edge list, with one `<u> <v>` edge per line between arbitrary
+/// vertex names, into an in-memory graph labeled by those names. Vertices
+/// are numbered in order of their first appearance, going through the files
+/// in order. Empty lines, lines starting with `#` or `%`, and self loops are
+/// skipped.
+pub fn read_labeled_edgelist(scanner: &Scanner) -> LabeledGraph<String> {
+    // at most one edge per line, so this spares regrowing each file's edges
+    let capacities = scanner.line_counts();
+    let edges = scanner
+        .fold(
+            |i| Vec::with_capacity(capacities[i]),
+            |mut edges, line| {
+                edges.extend(parse_labeled_edge(line));
+                edges
+            },
+        )
+        .collect::<Vec<Vec<_>>>()
+        .concat();
+
+    let mut labels = Vec::new();
+    let mut ids: HashMap<String, u32> = HashMap::new();
+    let mut id = |label: String| {
+        *ids.entry(label).or_insert_with_key(|label| {
+            labels.push(label.clone());
+            labels.len() as u32 - 1
+        })
+    };
+    let edges: Vec<(u32, u32)> = edges.into_iter().map(|(u, v)| (id(u), id(v))).collect();
+    let graph = Graph::from_edge_pairs(labels.len(), &edges);
+    LabeledGraph::new(graph, labels)
+}
+
+fn parse_labeled_edge(line: DelimIter<'_>) -> Option<(String, String)> {
+    let first = line.clone().next()?;
+    if first.starts_with(b"#") || first.starts_with(b"%") {
+        return None;
+    }
+    let mut words = line.clone().filter(|w| !w.is_empty());
+    match (words.next(), words.next(), words.next()) {
+        (Some(u), Some(v), None) => {
+            Some((u.to_str_lossy().into_owned(), v.to_str_lossy().into_owned()))
+        }
+        _ => panic!("expected edge `<u> <v>`, got `{}`", line.dbg_line()),
+    }
+}
+
+/// Reads a weighted edge list, with one `<u> <v> <weight>` edge per line,
+/// into an in-memory graph. Empty lines and lines starting with `#` or `%`
+/// are skipped, as are self loops. An edge listed more than once keeps its
+/// smallest weight.
+pub fn read_weighted(scanner: &Scanner) -> WeightedGraph {
+    let capacities = scanner.line_counts();
+    let edges = scanner
+        .fold(
+            |i| Vec::with_capacity(capacities[i]),
+            |mut edges, line| {
+                edges.extend(parse_weighted_edge(scanner, line));
+                edges
+            },
+        )
+        .collect::<Vec<Vec<_>>>()
+        .concat();
+    let nvertices = 1 + edges
+        .iter()
+        .map(|&(u, v, _)| u.max(v) as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut lists = vec![Vec::new(); nvertices];
+    for (u, v, weight) in edges {
+        if u != v {
+            lists[u as usize].push((v, weight));
+            lists[v as usize].push((u, weight));
+        }
+    }
+    lists.par_iter_mut().for_each(|list| {
+        list.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        list.dedup_by_key(|e| e.0);
+    });
+
+    let mut offsets = Vec::with_capacity(nvertices + 1);
+    let mut neighbors = Vec::new();
+    let mut weights = Vec::new();
+    offsets.push(0);
+    for list in lists {
+        for (v, weight) in list {
+            neighbors.push(v);
+            weights.push(weight);
+        }
+        offsets.push(neighbors.len());
+    }
+    if scanner.parse_error_count() > 0 {
+        println!(
+            "{}",
+            json!({
+                "warning": "skipped malformed lines",
+                "parse_errors": scanner.parse_error_count(),
+            })
+        );
+    }
+    WeightedGraph::new(offsets, neighbors, weights)
+}
+
+/// Parses a `<u> <v> <weight>` line, returning `None` for comments and
+/// empty lines. Malformed lines panic, unless the scanner is lenient, in
+/// which case they're recorded and skipped.
+fn parse_weighted_edge(scanner: &Scanner, line: DelimIter<'_>) -> Option<(u32, u32, f32)> {
+    let first = line.clone().next()?;
+    if first.starts_with(b"#") || first.starts_with(b"%") {
+        return None;
+    }
+    let mut words = line
+        .clone()
+        .map(|w| std::str::from_utf8(w).ok())
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default()
+        .into_iter();
+    let edge = match (words.next(), words.next(), words.next(), words.next()) {
+        (Some(u), Some(v), Some(weight), None) => u
+            .parse::<u32>()
+            .ok()
+            .zip(v.parse::<u32>().ok())
+            .zip(weight.parse::<f32>().ok().filter(|w| !w.is_nan()))
+            .map(|((u, v), weight)| (u, v, weight)),
+        _ => None,
+    };
+    if edge.is_none() {
+        assert!(
+            scanner.is_lenient(),
+            "expected weighted edge `<u> <v> <weight>`, got `{}`",
+            line.dbg_line()
+        );
+        scanner.record_parse_error();
+    }
+    edge
+}
+
+/// Writes a graph as an edge list readable by [`read_edgelist`], with each
+/// edge `(u, v)` written once, as `u v` with `u < v`.
+pub fn write_edgelist(graph: &Graph, out: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(out)?);
+    for (u, v) in graph.edges() {
+        writeln!(writer, "{} {}", u, v)?;
+    }
+    writer.flush()
+}
+
+fn parse_adjacency(line: DelimIter<'_>) -> Option<SimSvmLineIter<'_>> {
+    Some(simsvm::parse(line))
+}
+
+fn parse_edge(line: DelimIter<'_>) -> Option<SimSvmLineIter<'_>> {
+    let first = line.clone().next()?;
+    if first.starts_with(b"#") || first.starts_with(b"%") {
+        return None;
+    }
+    assert!(
+        line.clone().count() == 2,
+        "expected edge `<u> <v>`, got `{}`",
+        line.dbg_line()
+    );
+    Some(simsvm::parse(line))
+}
+
+type LineParser = fn(DelimIter<'_>) -> Option<SimSvmLineIter<'_>>;
+
+/// Applies `parse`, except that lenient scanners skip lines with a word that
+/// isn't a `u32`, or no words at all, recording a parse error if
+/// `record_error` and the line isn't a `#` or `%` comment.
+fn parse_line<'a>(
+    scanner: &Scanner,
+    parse: LineParser,
+    line: DelimIter<'a>,
+    record_error: bool,
+) -> Option<SimSvmLineIter<'a>> {
+    if scanner.is_lenient() && is_malformed(line.clone()) {
+        let comment = line
+            .clone()
+            .next()
+            .is_some_and(|w| w.starts_with(b"#") || w.starts_with(b"%"));
+        if record_error && !comment {
+            scanner.record_parse_error();
+        }
+        return None;
+    }
+    parse(line)
+}
+
+fn is_malformed(mut line: DelimIter<'_>) -> bool {
+    let mut empty = true;
+    let bad_word = line.any(|word| {
+        empty = false;
+        std::str::from_utf8(word)
+            .ok()
+            .and_then(|w| w.parse::<u32>().ok())
+            .is_none()
+    });
+    empty || bad_word
+}
+
+/// One more than the largest vertex in the lines produced by `parse`.
+fn count_vertices(scanner: &Scanner, parse: LineParser) -> usize {
+    1 + scanner
         .fold(
             |_| 0,
             |m, line| {
-                let line = simsvm::parse(line);
+                let line = match parse_line(scanner, parse, line, false) {
+                    Some(line) => line,
+                    None => return m,
+                };
                 let target: u32 = line.target();
                 line.max().unwrap_or(0).max(target).max(m)
             },
         )
         .max()
-        .unwrap_or(0) as usize;
-
-    // TODO: this gets confusing if duplicate edges are present: maybe worth
-    // deduping?
+        .unwrap_or(0) as usize
+}
 
+/// Reads lines of a target vertex followed by its neighbors, as produced by
+/// `parse`, which may skip a line by returning `None`, into a graph over
+/// `[0, nvertices)`, cleaning up edges as set by `options`.
+fn read_lines(
+    scanner: &Scanner,
+    parse: LineParser,
+    nvertices: usize,
+    options: ReadOptions,
+) -> Graph {
     // if you *really* want this to crank then swap out the atomics for sharded owners
     // and use mpsc queues to pass around increment/store messages
     let (offsets, mut edges, offset_time, edge_time) = {
@@ -42,12 +437,27 @@ pub fn read(scanner: &Scanner) -> Graph {
             .fold(
                 |_| (),
                 |_, line| {
-                    let line = simsvm::parse(line);
+                    let line = match parse_line(scanner, parse, line, true) {
+                        Some(line) => line,
+                        None => return,
+                    };
                     let target: u32 = line.target();
-                    for neighbor in line {
+                    assert!(
+                        (target as usize) < nvertices,
+                        "vertex {} out of range for {} vertices",
+                        target,
+                        nvertices
+                    );
+                    line.for_each_feature(|neighbor| {
+                        assert!(
+                            (neighbor as usize) < nvertices,
+                            "vertex {} out of range for {} vertices",
+                            neighbor,
+                            nvertices
+                        );
                         atomic_offsets[1 + neighbor as usize].fetch_add(1, Ordering::Relaxed);
                         atomic_offsets[1 + target as usize].fetch_add(1, Ordering::Relaxed);
-                    }
+                    });
                 },
             )
             .collect::<()>();
@@ -73,16 +483,19 @@ pub fn read(scanner: &Scanner) -> Graph {
             .fold(
                 |_| (),
                 |_, line| {
-                    let line = simsvm::parse(line);
+                    let line = match parse_line(scanner, parse, line, false) {
+                        Some(line) => line,
+                        None => return,
+                    };
                     let target = line.target();
-                    for neighbor in line {
+                    line.for_each_feature(|neighbor| {
                         let target_ix =
                             atomic_offsets[target as usize].fetch_add(1, Ordering::Relaxed);
                         let neighbor_ix =
                             atomic_offsets[neighbor as usize].fetch_add(1, Ordering::Relaxed);
                         atomic_edges[target_ix].store(neighbor, Ordering::Relaxed);
                         atomic_edges[neighbor_ix].store(target, Ordering::Relaxed);
-                    }
+                    });
                 },
             )
             .collect::<()>();
@@ -104,7 +517,7 @@ pub fn read(scanner: &Scanner) -> Graph {
         let mut head_and_tail = edges.split_at_mut(0);
         let mut neighbor_lists = Vec::with_capacity(offsets.len() - 1);
         for s in offsets.windows(2) {
-            let next_chunk = (s[1] - s[0]) as usize;
+            let next_chunk = s[1] - s[0];
             head_and_tail = head_and_tail.1.split_at_mut(next_chunk);
             neighbor_lists.push(head_and_tail.0);
         }
@@ -117,6 +530,31 @@ pub fn read(scanner: &Scanner) -> Graph {
         (slice_build_time, sort_time)
     };
 
+    // repeated lines or edges listed from both ends would otherwise break the
+    // graph invariants
+    let dedup_start = Instant::now();
+    let (offsets, edges) = if options.deduplicate {
+        Graph::dedup_edges(offsets, edges)
+    } else {
+        (offsets, edges)
+    };
+    let (offsets, edges) = if options.remove_self_loops {
+        Graph::remove_self_loops(offsets, edges)
+    } else {
+        (offsets, edges)
+    };
+    let dedup_time = format!("{:.0?}", Instant::now().duration_since(dedup_start));
+
+    if scanner.parse_error_count() > 0 {
+        println!(
+            "{}",
+            json!({
+                "warning": "skipped malformed lines",
+                "parse_errors": scanner.parse_error_count(),
+            })
+        );
+    }
+
     println!(
         "{}",
         json!({
@@ -124,8 +562,258 @@ pub fn read(scanner: &Scanner) -> Graph {
             "edge_time": edge_time,
             "offset_time": offset_time,
             "slice_build_time": slice_build_time,
+            "dedup_time": dedup_time,
         })
     );
 
     Graph::new(offsets, edges)
 }
+
+/// Writes a graph in the format consumed by [`read`], sharded across files
+/// `<out_prefix>.0`, `<out_prefix>.1`, ..., each holding up to
+/// `lines_per_file` consecutive vertices, written in parallel.
+///
+/// Every vertex gets a line, listing only its neighbors with larger ids so
+/// that each edge is written once.
+pub fn write(graph: &Graph, out_prefix: &Path, lines_per_file: usize) -> io::Result<()> {
+    assert!(lines_per_file > 0);
+    let n = graph.nvertices();
+    let nfiles = n.div_ceil(lines_per_file);
+    (0..nfiles).into_par_iter().try_for_each(|file_ix| {
+        let lo = file_ix * lines_per_file;
+        let hi = ((file_ix + 1) * lines_per_file).min(n);
+        write_shard(graph, &shard_path(out_prefix, file_ix), lo..hi)
+    })
+}
+
+/// Like [`write`], but splits the graph into `nshards` files holding runs of
+/// consecutive vertices with roughly equal numbers of edges, rather than of
+/// vertices, so that shards of graphs with skewed degrees are about as fast
+/// to read. Returns the paths of the shards, some of which may be empty.
+pub fn shard_graph(graph: &Graph, nshards: usize, out_prefix: &Path) -> io::Result<Vec<PathBuf>> {
+    assert!(nshards > 0);
+    let n = graph.nvertices();
+    // each line costs its vertex plus the neighbors it lists
+    let mut bounds = Vec::with_capacity(nshards + 1);
+    bounds.push(0);
+    let total: usize = n + graph.nedges();
+    let mut cost = 0;
+    for v in 0..n {
+        let target = total * bounds.len() / nshards;
+        if cost >= target && bounds.len() < nshards {
+            bounds.push(v);
+        }
+        let v = v as u32;
+        cost += 1 + graph.neighbors(v).iter().filter(|&&w| w > v).count();
+    }
+    bounds.resize(nshards + 1, n);
+
+    let paths: Vec<PathBuf> = (0..nshards).map(|i| shard_path(out_prefix, i)).collect();
+    paths
+        .par_iter()
+        .zip(bounds.par_windows(2))
+        .try_for_each(|(path, s)| write_shard(graph, path, s[0]..s[1]))?;
+    Ok(paths)
+}
+
+/// Like [`shard_graph`], but rather than keeping runs of consecutive
+/// vertices together, assigns vertices to `nshards` files
+/// `<out_prefix>.0`, `<out_prefix>.1`, ... to even out the number of edges
+/// each file lists, in parallel.
+///
+/// Vertices go, in decreasing order of the edges their lines list, to the
+/// file with the fewest so far, which keeps the largest file within a
+/// vertex's line of the smallest. Each file lists its vertices in
+/// increasing order.
+pub fn write_balanced_shards(graph: &Graph, nshards: usize, out_prefix: &Path) -> io::Result<()> {
+    assert!(nshards > 0);
+    let n = graph.nvertices();
+    let cost = |v: usize| {
+        let v = v as u32;
+        1 + graph.neighbors(v).iter().filter(|&&w| w > v).count()
+    };
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&v| Reverse(cost(v)));
+
+    let mut loads: BinaryHeap<Reverse<(usize, usize)>> =
+        (0..nshards).map(|i| Reverse((0, i))).collect();
+    let mut shards = vec![Vec::new(); nshards];
+    for v in order {
+        let Reverse((load, i)) = loads.pop().unwrap();
+        shards[i].push(v);
+        loads.push(Reverse((load + cost(v), i)));
+    }
+
+    shards
+        .par_iter_mut()
+        .enumerate()
+        .try_for_each(|(i, vertices)| {
+            vertices.sort_unstable();
+            write_shard(graph, &shard_path(out_prefix, i), vertices.iter().copied())
+        })
+}
+
+/// `<out_prefix>.<i>`
+fn shard_path(out_prefix: &Path, i: usize) -> PathBuf {
+    let mut fname = out_prefix.file_name().expect("file name").to_owned();
+    fname.push(format!(".{}", i));
+    out_prefix.with_file_name(fname)
+}
+
+/// Writes the lines of [`write`] for `vertices` to `path`.
+fn write_shard(
+    graph: &Graph,
+    path: &Path,
+    vertices: impl IntoIterator<Item = usize>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for v in vertices {
+        let v = v as u32;
+        write!(writer, "{}", v)?;
+        for &w in graph.neighbors(v).iter().filter(|&&w| w > v) {
+            write!(writer, " {}", w)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// The files [`write`] produced for `out_prefix`, in order.
+    fn shards(out_prefix: &Path) -> Scanner {
+        let paths = (0..)
+            .map(|i| PathBuf::from(format!("{}.{}", out_prefix.display(), i)))
+            .take_while(|path| path.exists())
+            .collect();
+        Scanner::new(paths, b' ')
+    }
+
+    fn lines(path: &Path) -> Scanner {
+        Scanner::new(vec![path.to_owned()], b' ')
+    }
+
+    #[test]
+    fn write_then_read_roundtrip() {
+        let dir = crate::test_dir("write_then_read_roundtrip");
+        let graph = crate::generators::watts_strogatz(300, 6, 0.3, 2).unwrap();
+        let prefix = dir.join("graph");
+        write(&graph, &prefix, 17).unwrap();
+        let scanner = shards(&prefix);
+        assert_eq!(scanner.line_count(), graph.nvertices());
+        assert_eq!(estimate_nvertices(&scanner), graph.nvertices());
+        assert_eq!(read(&scanner), graph);
+    }
+
+    #[test]
+    fn edgelist_roundtrip() {
+        let dir = crate::test_dir("edgelist_roundtrip");
+        let path = dir.join("edges.txt");
+        fs::write(&path, "# a comment\n0 1\n2 1\n\n% another\n0 2\n3 2\n").unwrap();
+        let graph = read_edgelist(&lines(&path));
+        assert_eq!(
+            graph,
+            Graph::from_edge_pairs(4, &[(0, 1), (1, 2), (0, 2), (2, 3)])
+        );
+
+        let graph = crate::generators::watts_strogatz(200, 4, 0.3, 3).unwrap();
+        let out = dir.join("written.txt");
+        write_edgelist(&graph, &out).unwrap();
+        assert_eq!(lines(&out).line_count(), graph.nedges());
+        assert_eq!(read_edgelist(&lines(&out)), graph);
+    }
+
+    #[test]
+    fn duplicate_edges_are_dropped() {
+        let dir = crate::test_dir("duplicate_edges_are_dropped");
+        let path = dir.join("graph.txt");
+        fs::write(&path, "0 1 1 2\n1 0\n0 1\n2 2 0\n").unwrap();
+        let graph = read(&lines(&path));
+        assert_eq!(graph.nedges(), 2);
+        assert_eq!(graph, Graph::from_edge_pairs(3, &[(0, 1), (0, 2)]));
+    }
+
+    #[test]
+    fn trailing_isolated_vertices() {
+        let dir = crate::test_dir("trailing_isolated_vertices");
+        let path = dir.join("graph.txt");
+        fs::write(&path, "0 1\n1 0\n").unwrap();
+        assert_eq!(read(&lines(&path)), read_with_nvertices(&lines(&path), 2));
+        let graph = read_with_nvertices(&lines(&path), 5);
+        assert_eq!(graph, Graph::from_edge_pairs(5, &[(0, 1)]));
+    }
+
+    #[test]
+    fn malformed_lines_report_position() {
+        let dir = crate::test_dir("malformed_lines_report_position");
+        let path = dir.join("bad.txt");
+        fs::write(&path, "0 1 2\n1 2\n2 x3\n").unwrap();
+        match try_read(&lines(&path)) {
+            Err(GraphIoError::ParseError {
+                file, line, column, ..
+            }) => {
+                assert_eq!(file, path);
+                assert_eq!((line, column), (3, 3));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        fs::write(&path, "0 1\n\n").unwrap();
+        assert!(matches!(
+            try_read(&lines(&path)),
+            Err(GraphIoError::ParseError { line: 2, .. })
+        ));
+        assert!(matches!(
+            try_read(&lines(&dir.join("missing.txt"))),
+            Err(GraphIoError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn lenient_read_skips_bad_line() {
+        let dir = crate::test_dir("lenient_read_skips_bad_line");
+        let path = dir.join("graph.txt");
+        fs::write(&path, "0 1 2\n1 2\n2 3 oops\n3 4\n").unwrap();
+        let scanner = Scanner::new_lenient(vec![path], b' ');
+        let graph = read(&scanner);
+        assert_eq!(scanner.parse_error_count(), 1);
+        assert_eq!(
+            graph,
+            Graph::from_edge_pairs(5, &[(0, 1), (0, 2), (1, 2), (3, 4)])
+        );
+    }
+
+    #[test]
+    fn shards_read_back() {
+        let dir = crate::test_dir("shards_read_back");
+        let graph = crate::generators::stochastic_block_model(&[50, 200], 0.3, 0.01, 4);
+        let prefix = dir.join("graph");
+        let paths = shard_graph(&graph, 5, &prefix).unwrap();
+        assert_eq!(paths.len(), 5);
+        assert_eq!(read(&Scanner::new(paths, b' ')), graph);
+    }
+
+    #[test]
+    fn balanced_shards_are_even() {
+        let dir = crate::test_dir("balanced_shards_are_even");
+        // a hub adjacent to a third of an otherwise sparse graph
+        let n = 3000;
+        let sparse = crate::generators::watts_strogatz(n, 6, 0.3, 5).unwrap();
+        let mut edges: Vec<_> = sparse.edges().collect();
+        edges.extend((1..n / 3).map(|v| (0, v)));
+        let graph = Graph::from_edge_pairs(n as usize, &edges);
+        let prefix = dir.join("graph");
+        write_balanced_shards(&graph, 4, &prefix).unwrap();
+
+        let sizes: Vec<u64> = (0..4)
+            .map(|i| fs::metadata(shard_path(&prefix, i)).unwrap().len())
+            .collect();
+        let (min, max) = (sizes.iter().min().unwrap(), sizes.iter().max().unwrap());
+        assert!(*max <= 2 * min, "{:?}", sizes);
+        assert_eq!(read(&shards(&prefix)), graph);
+    }
+}
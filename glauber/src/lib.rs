@@ -4,35 +4,174 @@
 //! plaintext files of ints.
 
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
 
+use itertools::Itertools;
 use ordered_float::NotNan;
+use rand::SeedableRng;
+use rand_pcg::Lcg64Xsh32;
+use rayon::iter::ParallelIterator;
+use serde::ser::{Serialize, SerializeMap, Serializer};
 
 mod atomic_rw;
 pub mod color;
+pub mod generators;
 pub mod graph;
 pub mod graphio;
 mod scanner;
 pub mod simsvm;
 
-pub use scanner::{DelimIter, Scanner};
+pub use scanner::{DelimIter, GlobError, Scanner};
+
+/// The generator behind every seeded random choice in this crate, so that the
+/// same seed always reproduces the same run.
+pub fn seeded_rng(seed: u64) -> Lcg64Xsh32 {
+    Lcg64Xsh32::seed_from_u64(seed)
+}
+
+/// Seed for the `i`-th of several parallel streams (sampler threads, chains,
+/// or walks) of a run seeded with `seed`, spreading out the index with a
+/// 64-bit LCG multiplier so that neighboring streams don't get correlated
+/// generators.
+pub(crate) fn thread_seed(seed: u64, i: usize) -> u64 {
+    seed ^ (i as u64).wrapping_mul(6364136223846793005)
+}
+
+/// A fresh, empty scratch directory for the test `name`.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("glauber-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create test dir");
+    dir
+}
 
 const NSTAT_PERCENTILES: usize = 11;
 const STAT_PERCENTILES: [f64; NSTAT_PERCENTILES] = [
     0.0, 0.001, 0.01, 0.05, 0.10, 0.50, 0.90, 0.95, 0.99, 0.99, 1.0,
 ];
+/// Column names for [`STAT_PERCENTILES`], matching the `p{:.3}` keys of
+/// [`SummaryStats::to_map`].
+const STAT_PERCENTILE_NAMES: [&str; NSTAT_PERCENTILES] = [
+    "p0.000", "p0.001", "p0.010", "p0.050", "p0.100", "p0.500", "p0.900", "p0.950", "p0.990",
+    "p0.990", "p1.000",
+];
+
+/// How [`SummaryStats::try_from`] treats NaN inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// Fail on the first NaN.
+    Reject,
+    /// Drop NaNs from the input.
+    Skip,
+    /// Count each NaN as a zero.
+    ReplaceWithZero,
+}
+
+/// Errors from [`SummaryStats::try_from`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum StatsError {
+    /// The input contained a NaN under [`NanPolicy::Reject`].
+    Nan {
+        /// Position of the first NaN in the input.
+        index: usize,
+    },
+    /// No values were left to summarize, e.g., because the input was empty
+    /// or all NaN under [`NanPolicy::Skip`].
+    Empty,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nan { index } => write!(f, "NaN at input index {}", index),
+            Self::Empty => write!(f, "no values to summarize"),
+        }
+    }
+}
+
+impl Error for StatsError {}
 
 pub struct SummaryStats {
     mean: f64,
     percentiles: [f64; NSTAT_PERCENTILES],
+    count: usize,
+    std_dev: f64,
 }
 
 impl SummaryStats {
+    /// Summarizes the values, panicking on NaNs or if there are none. The
+    /// other constructors panic on those too, except for
+    /// [`SummaryStats::try_from`].
     pub fn from(it: impl Iterator<Item = f64>) -> Self {
         let mut v: Vec<NotNan<f64>> = it.map(|f| NotNan::new(f).unwrap()).collect();
         v.sort_unstable();
+        Self::from_sorted_vec(v)
+    }
+
+    /// Like [`SummaryStats::from`], but handles NaN inputs according to
+    /// `policy` rather than panicking, and fails if no values are left.
+    pub fn try_from(it: impl Iterator<Item = f64>, policy: NanPolicy) -> Result<Self, StatsError> {
+        let mut v = Vec::new();
+        for (index, f) in it.enumerate() {
+            match NotNan::new(f) {
+                Ok(f) => v.push(f),
+                Err(_) => match policy {
+                    NanPolicy::Reject => return Err(StatsError::Nan { index }),
+                    NanPolicy::Skip => {}
+                    NanPolicy::ReplaceWithZero => v.push(NotNan::from(0.0)),
+                },
+            }
+        }
+        if v.is_empty() {
+            return Err(StatsError::Empty);
+        }
+        v.sort_unstable();
+        Ok(Self::from_sorted_vec(v))
+    }
+
+    /// Like [`SummaryStats::from`], but skips sorting; the values must
+    /// already be in nondecreasing order.
+    pub fn from_sorted(it: impl Iterator<Item = f64>) -> Self {
+        let v: Vec<NotNan<f64>> = it.map(|f| NotNan::new(f).unwrap()).collect();
+        debug_assert!(
+            v.windows(2).all(|w| w[0] <= w[1]),
+            "from_sorted input is not sorted"
+        );
+        Self::from_sorted_vec(v)
+    }
+
+    /// Like [`SummaryStats::from`], but each rayon task sorts the values it
+    /// sees locally before the sorted runs are merged together.
+    pub fn from_parallel(it: impl ParallelIterator<Item = f64>) -> Self {
+        let v = it
+            .fold(Vec::new, |mut v, f| {
+                v.push(NotNan::new(f).unwrap());
+                v
+            })
+            .map(|mut v| {
+                v.sort_unstable();
+                v
+            })
+            .reduce(Vec::new, |a, b| a.into_iter().merge(b).collect());
+        Self::from_sorted_vec(v)
+    }
+
+    fn from_sorted_vec(v: Vec<NotNan<f64>>) -> Self {
+        assert!(!v.is_empty(), "summary stats of no values");
+        let mean = v.iter().map(|f| f.into_inner()).sum::<f64>() / v.len() as f64;
+        let var = v
+            .iter()
+            .map(|f| (f.into_inner() - mean) * (f.into_inner() - mean))
+            .sum::<f64>()
+            / v.len() as f64;
         let mut stats = SummaryStats {
-            mean: v.iter().map(|f| f.into_inner()).sum::<f64>() / v.len() as f64,
+            mean,
             percentiles: Default::default(),
+            count: v.len(),
+            std_dev: var.sqrt(),
         };
         STAT_PERCENTILES
             .iter()
@@ -52,4 +191,261 @@ impl SummaryStats {
         map.insert("mean".to_string(), self.mean);
         map
     }
+
+    /// Bins `values`, which should be the summarized values, into `nbins`
+    /// equal-width bins spanning their minimum to their maximum. The values
+    /// themselves aren't kept, so they're passed in again here.
+    pub fn histogram(&self, values: impl Iterator<Item = f64>, nbins: usize) -> Histogram {
+        let min = self.percentiles[0];
+        let max = self.percentiles[NSTAT_PERCENTILES - 1];
+        let mut hist = Histogram::new(min, max, nbins);
+        values.for_each(|f| hist.push(f));
+        hist
+    }
+
+    /// Number of summarized values.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Population standard deviation of the summarized values.
+    pub fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+
+    /// CSV column names, in the order [`SummaryStats::to_csv_row`] writes
+    /// values: `mean`, `count`, `std_dev`, then each distinct percentile.
+    pub fn headers() -> Vec<&'static str> {
+        let mut headers = vec!["mean", "count", "std_dev"];
+        headers.extend(STAT_PERCENTILE_NAMES.iter().copied().dedup());
+        headers
+    }
+
+    /// Comma-separated values for the columns of [`SummaryStats::headers`].
+    pub fn to_csv_row(&self) -> String {
+        let mut fields = vec![
+            self.mean.to_string(),
+            self.count().to_string(),
+            self.std_dev().to_string(),
+        ];
+        fields.extend(
+            STAT_PERCENTILE_NAMES
+                .iter()
+                .zip(self.percentiles.iter())
+                .dedup_by(|a, b| a.0 == b.0)
+                .map(|(_, p)| p.to_string()),
+        );
+        fields.join(",")
+    }
+
+    /// Writes the [`SummaryStats::headers`] line.
+    pub fn write_csv_header(w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "{}", Self::headers().join(","))
+    }
+
+    /// Returns the same entries as [`SummaryStats::to_map`] as a JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("summary stats json")
+    }
+}
+
+/// Counts of values falling into equal-width bins.
+pub struct Histogram {
+    bins: Vec<u64>,
+    bin_width: f64,
+    min: f64,
+}
+
+impl Histogram {
+    /// Creates `nbins` empty bins evenly covering `[min, max]`, or
+    /// `[min, min + 1]` if the two are equal.
+    pub fn new(min: f64, max: f64, nbins: usize) -> Self {
+        assert!(nbins > 0, "histogram needs at least one bin");
+        assert!(min <= max, "histogram min {} above max {}", min, max);
+        let span = if max > min { max - min } else { 1.0 };
+        Self {
+            bins: vec![0; nbins],
+            bin_width: span / nbins as f64,
+            min,
+        }
+    }
+
+    /// Counts `value` in its bin; values outside the range land in the
+    /// first or last bin.
+    pub fn push(&mut self, value: f64) {
+        let i = ((value - self.min) / self.bin_width) as usize;
+        let last = self.bins.len() - 1;
+        self.bins[i.min(last)] += 1;
+    }
+
+    /// Returns each bin's `(lo, hi, count)`, where the bin covers
+    /// `[lo, hi)`, in increasing order.
+    pub fn to_vec(&self) -> Vec<(f64, f64, u64)> {
+        let edge = |i: usize| self.min + self.bin_width * i as f64;
+        self.bins
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (edge(i), edge(i + 1), count))
+            .collect()
+    }
+}
+
+/// Serializes as a map from `mean` and percentile names like `p0.500` to
+/// values, with the same keys as [`SummaryStats::to_map`].
+impl Serialize for SummaryStats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(String, f64)> = STAT_PERCENTILES
+            .iter()
+            .map(|f| format!("p{:.3}", f))
+            .zip(self.percentiles.iter().copied())
+            .dedup_by(|a, b| a.0 == b.0)
+            .collect();
+        let mut map = serializer.serialize_map(Some(1 + entries.len()))?;
+        map.serialize_entry("mean", &self.mean)?;
+        for (k, v) in &entries {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Pretty-prints the JSON from [`SummaryStats::to_json`].
+impl fmt::Display for SummaryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::Rng;
+    use rayon::iter::IntoParallelRefIterator;
+
+    use super::*;
+
+    fn random_values(n: usize) -> Vec<f64> {
+        let mut rng = seeded_rng(0);
+        (0..n).map(|_| rng.gen_range(-100.0..100.0)).collect()
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let values = random_values(1_000_000);
+        let sequential = SummaryStats::from(values.iter().copied());
+        let parallel = SummaryStats::from_parallel(values.par_iter().copied());
+        assert_eq!(parallel.to_map(), sequential.to_map());
+    }
+
+    /// Compares [`SummaryStats::from`] against [`SummaryStats::from_parallel`]
+    /// over 10M values. Run with
+    /// `cargo test --release -- --ignored --nocapture summary`.
+    #[test]
+    #[ignore]
+    fn bench_summary() {
+        let values = random_values(10_000_000);
+
+        let start = Instant::now();
+        let sequential = SummaryStats::from(values.iter().copied());
+        let sequential_time = Instant::now().duration_since(start);
+
+        let start = Instant::now();
+        let parallel = SummaryStats::from_parallel(values.par_iter().copied());
+        let parallel_time = Instant::now().duration_since(start);
+
+        assert_eq!(parallel.to_map(), sequential.to_map());
+        println!(
+            "from {:.0?} from_parallel {:.0?} threads {}",
+            sequential_time,
+            parallel_time,
+            rayon::current_num_threads()
+        );
+    }
+
+    #[test]
+    fn json_matches_map() {
+        let stats = SummaryStats::from(random_values(100).into_iter());
+        let json = stats.to_json();
+        for (key, value) in stats.to_map() {
+            assert_eq!(json[&key].as_f64(), Some(value), "{}", key);
+        }
+        // parsing back may be off in the last digit
+        let displayed: serde_json::Value = serde_json::from_str(&stats.to_string()).unwrap();
+        let displayed = displayed.as_object().unwrap();
+        assert_eq!(displayed.len(), json.as_object().unwrap().len());
+        for (key, value) in stats.to_map() {
+            let shown = displayed[&key].as_f64().unwrap();
+            assert!((shown - value).abs() <= 1e-12 * value.abs(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn from_sorted_matches_from() {
+        let mut values = random_values(10_000);
+        values.sort_by(f64::total_cmp);
+        let sorted = SummaryStats::from_sorted(values.iter().copied());
+        let unsorted = SummaryStats::from(values.iter().rev().copied());
+        assert_eq!(sorted.to_map(), unsorted.to_map());
+    }
+
+    #[test]
+    fn histogram_counts_every_value() {
+        let values = random_values(10_000);
+        let stats = SummaryStats::from(values.iter().copied());
+        for nbins in [1, 7, 100] {
+            let hist = stats.histogram(values.iter().copied(), nbins);
+            assert_eq!(hist.bins.len(), nbins);
+            assert_eq!(hist.bins.iter().sum::<u64>(), 10_000);
+            let bins = hist.to_vec();
+            assert_eq!(bins.iter().map(|&(_, _, count)| count).sum::<u64>(), 10_000);
+            assert!(bins.windows(2).all(|w| w[0].1 == w[1].0));
+        }
+        let constant = SummaryStats::from(std::iter::repeat_n(3.0, 10));
+        let hist = constant.histogram(std::iter::repeat_n(3.0, 10), 4);
+        assert_eq!(hist.bins, vec![10, 0, 0, 0]);
+        // bins narrower than any fixed precision stay distinct
+        let narrow = Histogram::new(0.0, 1e-6, 10).to_vec();
+        assert_eq!(narrow.len(), 10);
+        assert!(narrow.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn nan_policies() {
+        let values = [1.0, f64::NAN, 3.0, 2.0, f64::NAN];
+        let skipped = SummaryStats::try_from(values.iter().copied(), NanPolicy::Skip).unwrap();
+        let filtered = SummaryStats::from(values.iter().copied().filter(|f| !f.is_nan()));
+        assert_eq!(skipped.to_map(), filtered.to_map());
+        let zeroed =
+            SummaryStats::try_from(values.iter().copied(), NanPolicy::ReplaceWithZero).unwrap();
+        assert_eq!(zeroed.to_map()["p0.000"], 0.0);
+        let err = SummaryStats::try_from(values.iter().copied(), NanPolicy::Reject);
+        assert_eq!(err.err(), Some(StatsError::Nan { index: 1 }));
+
+        let nans = [f64::NAN; 3];
+        let skipped = SummaryStats::try_from(nans.iter().copied(), NanPolicy::Skip);
+        assert_eq!(skipped.err(), Some(StatsError::Empty));
+        for policy in [
+            NanPolicy::Reject,
+            NanPolicy::Skip,
+            NanPolicy::ReplaceWithZero,
+        ] {
+            let empty = SummaryStats::try_from(std::iter::empty(), policy);
+            assert_eq!(empty.err(), Some(StatsError::Empty));
+        }
+    }
+
+    #[test]
+    fn csv_header_matches_row() {
+        let stats = SummaryStats::from(random_values(100).into_iter());
+        let row = stats.to_csv_row();
+        assert_eq!(SummaryStats::headers().len(), row.split(',').count());
+        let mut header = Vec::new();
+        SummaryStats::write_csv_header(&mut header).unwrap();
+        let header = String::from_utf8(header).unwrap();
+        assert_eq!(header.trim_end().split(',').count(), row.split(',').count());
+        let constant = SummaryStats::from(std::iter::repeat_n(2.0, 10));
+        assert_eq!((constant.count(), constant.std_dev()), (10, 0.0));
+    }
 }
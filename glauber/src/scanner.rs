@@ -6,13 +6,18 @@
 //! can refered to shared structures in common memory between
 //! processing threads.
 
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
-use std::io::{BufRead, BufReader, BufWriter};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use bstr::ByteSlice;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::ParallelSlice;
+use smallvec::SmallVec;
 
 const BUFSIZE: usize = 64 * 1024;
 
@@ -34,6 +39,21 @@ impl<'a> DelimIter<'a> {
         }
     }
 
+    /// Parses each remaining word as a `u32`, panicking on malformed words,
+    /// and passes it to `f`.
+    pub fn for_each_parsed_u32<F: FnMut(u32)>(&mut self, mut f: F) {
+        for word in self {
+            let string = std::str::from_utf8(word).expect("utf-8");
+            f(string.parse().expect("parse u32"));
+        }
+    }
+
+    /// Collects the remaining words, without allocating for lines with at
+    /// most `N` of them.
+    pub fn collect_smallvec<const N: usize>(&mut self) -> SmallVec<[&'a [u8]; N]> {
+        self.collect()
+    }
+
     /// Assuming contents are utf8, returns them.
     #[allow(dead_code)]
     pub(crate) fn dbg_line(&self) -> String {
@@ -64,6 +84,52 @@ impl<'a> Iterator for DelimIter<'a> {
     }
 }
 
+/// Errors from [`Scanner::from_glob`].
+#[derive(Debug)]
+pub enum GlobError {
+    Io(io::Error),
+    Pattern(glob::PatternError),
+    NoMatches(String),
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Pattern(e) => write!(f, "bad glob pattern: {}", e),
+            Self::NoMatches(pattern) => write!(f, "no files match `{}`", pattern),
+        }
+    }
+}
+
+impl Error for GlobError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Pattern(e) => Some(e),
+            Self::NoMatches(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for GlobError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<glob::PatternError> for GlobError {
+    fn from(e: glob::PatternError) -> Self {
+        Self::Pattern(e)
+    }
+}
+
+impl From<glob::GlobError> for GlobError {
+    fn from(e: glob::GlobError) -> Self {
+        Self::Io(e.into())
+    }
+}
+
 /// A `Scanner` provides efficient line-level access to underlying files of
 /// words, where words are delimited with a specified delimiter.
 ///
@@ -72,12 +138,179 @@ impl<'a> Iterator for DelimIter<'a> {
 /// being iterated over.
 pub struct Scanner {
     paths: Vec<PathBuf>,
+    /// For each path, the byte range of it to read, or `None` for all of it.
+    ranges: Vec<Option<(u64, u64)>>,
     delimiter: u8,
+    lenient: bool,
+    parse_errors: AtomicUsize,
 }
 
 impl Scanner {
     pub fn new(paths: Vec<PathBuf>, delimiter: u8) -> Self {
-        Self { paths, delimiter }
+        Self {
+            ranges: vec![None; paths.len()],
+            paths,
+            delimiter,
+            lenient: false,
+            parse_errors: AtomicUsize::new(0),
+        }
+    }
+
+    /// Scans the files matching the glob `pattern` in sorted order, failing
+    /// if there are none, e.g., `/data/graph-*.txt`. Directories matching the
+    /// pattern are skipped.
+    pub fn from_glob(pattern: &str, delimiter: u8) -> Result<Self, GlobError> {
+        let mut paths = Vec::new();
+        for path in glob::glob(pattern)? {
+            let path = path?;
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() {
+            return Err(GlobError::NoMatches(pattern.to_owned()));
+        }
+        paths.sort();
+        Ok(Self::new(paths, delimiter))
+    }
+
+    /// Scans the single file at `path`, split into `nchunks` line-aligned
+    /// byte ranges which are read in parallel as if they were separate files.
+    ///
+    /// Line indices passed by [`Scanner::try_fold`] are relative to the start
+    /// of each chunk, and the `for_each_sink` family of methods, which write
+    /// one output per file, are unsupported.
+    pub fn from_single_file_parallel(path: PathBuf, nchunks: usize, delimiter: u8) -> Self {
+        assert!(nchunks > 0, "need at least one chunk");
+        let bounds = line_aligned_bounds(&path, nchunks);
+        Self {
+            ranges: bounds.windows(2).map(|r| Some((r[0], r[1]))).collect(),
+            ..Self::new(vec![path; nchunks], delimiter)
+        }
+    }
+
+    /// Like [`Scanner::new`], but readers built on this scanner, such as
+    /// [`crate::graphio::read`], skip lines that fail to parse instead of
+    /// panicking, tallying them in [`Scanner::parse_error_count`].
+    pub fn new_lenient(paths: Vec<PathBuf>, delimiter: u8) -> Self {
+        Self {
+            lenient: true,
+            ..Self::new(paths, delimiter)
+        }
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// The number of malformed lines skipped by readers over this scanner so
+    /// far, across all of their reads.
+    pub fn parse_error_count(&self) -> usize {
+        self.parse_errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_parse_error(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Opens the `i`-th file, positioned at the start of its range.
+    fn open(&self, i: usize) -> io::Result<io::Take<File>> {
+        let mut file = File::open(&self.paths[i])?;
+        Ok(match self.ranges[i] {
+            Some((start, end)) => {
+                file.seek(SeekFrom::Start(start))?;
+                file.take(end - start)
+            }
+            None => file.take(u64::MAX),
+        })
+    }
+
+    fn assert_whole_files(&self) {
+        assert!(
+            self.ranges.iter().all(Option::is_none),
+            "sinks are unsupported on chunked scanners"
+        );
+    }
+
+    /// Counts the lines across all files, in parallel over files, the same way
+    /// [`Scanner::fold`] splits them: a final line without a trailing newline
+    /// still counts.
+    pub fn line_count(&self) -> usize {
+        self.line_counts().into_iter().sum()
+    }
+
+    /// The [`Scanner::line_count`] of each file, in order, for sizing the
+    /// per-file accumulators of [`Scanner::fold`].
+    pub(crate) fn line_counts(&self) -> Vec<usize> {
+        self.paths
+            .par_iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let mut file = self
+                    .open(i)
+                    .unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+                let mut buf = vec![0u8; BUFSIZE];
+                let (mut count, mut last) = (0, b'\n');
+                loop {
+                    let nread = file.read(&mut buf).expect("line count read");
+                    if nread == 0 {
+                        break;
+                    }
+                    count += memchr::memchr_iter(b'\n', &buf[..nread]).count();
+                    last = buf[nread - 1];
+                }
+                count + (last != b'\n') as usize
+            })
+            .collect()
+    }
+
+    /// The largest `u32` which some line across the files starts with, in
+    /// parallel over files. Only the leading digits of each line are read,
+    /// skipping to the next newline with `memchr`.
+    pub(crate) fn max_leading_u32(&self) -> Option<u32> {
+        self.paths
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, path)| {
+                let mut file = self
+                    .open(i)
+                    .unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+                let mut buf = vec![0u8; BUFSIZE];
+                let mut max: Option<u32> = None;
+                // the leading number of the current line, while still in it
+                let mut leading: Option<Option<u32>> = Some(None);
+                loop {
+                    let nread = file.read(&mut buf).expect("leading word read");
+                    if nread == 0 {
+                        break;
+                    }
+                    let mut i = 0;
+                    while i < nread {
+                        match leading {
+                            Some(acc) if buf[i].is_ascii_digit() => {
+                                let digit = (buf[i] - b'0') as u32;
+                                leading = Some(Some(
+                                    acc.unwrap_or(0).saturating_mul(10).saturating_add(digit),
+                                ));
+                                i += 1;
+                            }
+                            Some(acc) => {
+                                max = max.max(acc);
+                                leading = None;
+                            }
+                            None => match memchr::memchr(b'\n', &buf[i..nread]) {
+                                Some(j) => {
+                                    i += j + 1;
+                                    leading = Some(None);
+                                }
+                                None => i = nread,
+                            },
+                        }
+                    }
+                }
+                max.max(leading.flatten())
+            })
+            .max()
     }
 
     /// Fold over the lines in the associated files to this scanner
@@ -101,7 +334,9 @@ impl Scanner {
     {
         let delim = self.delimiter;
         self.paths.par_iter().enumerate().map(move |(i, path)| {
-            let file = File::open(path).unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+            let file = self
+                .open(i)
+                .unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
             let reader = BufReader::with_capacity(BUFSIZE, file);
             reader.split(b'\n').fold(id(i), |acc, line| {
                 let line = line.expect("line read");
@@ -111,11 +346,42 @@ impl Scanner {
         })
     }
 
+    /// Like [`Scanner::fold`], but `fold` may fail, and is also passed the
+    /// path of the file and the zero-based index of the line within it.
+    ///
+    /// Each file stops at its first error, including errors opening or
+    /// reading it.
+    pub(crate) fn try_fold<'a, U, E, Id, Fold>(
+        &'a self,
+        id: Id,
+        fold: Fold,
+    ) -> impl ParallelIterator<Item = Result<U, E>> + 'a
+    where
+        U: Send,
+        E: From<io::Error> + Send,
+        Id: Fn(usize) -> U + Sync + Send + 'a,
+        Fold: Fn(U, DelimIter<'_>, &Path, usize) -> Result<U, E> + Sync + Send + 'a,
+    {
+        let delim = self.delimiter;
+        self.paths.par_iter().enumerate().map(move |(i, path)| {
+            let file = self.open(i)?;
+            let reader = BufReader::with_capacity(BUFSIZE, file);
+            let mut acc = id(i);
+            for (lineno, line) in reader.split(b'\n').enumerate() {
+                let line = line?;
+                acc = fold(acc, DelimIter::new(&line, delim), path, lineno)?;
+            }
+            Ok(acc)
+        })
+    }
+
     /// Map over lines in the associated files, writing to a sink for each file.
     ///
     /// A (cloneable) one-pass iterator is provided over each line's words
     /// is passed per `apply` invocation. You should write out just the contents
-    /// and any newlines you'd like to add yourself.
+    /// and any newlines you'd like to add yourself. `apply` writes into a
+    /// per-line buffer, which is only copied to the sink if it returns `true`,
+    /// so returning `false` filters the line out.
     ///
     /// Creates a new file, one for each input path in this `SvmScanner`, in the
     /// same directory as the input files, with an additional suffix. I.e., if we
@@ -125,24 +391,306 @@ impl Scanner {
     /// Common aggregation state is folded over for each file
     pub fn for_each_sink<Apply, T>(&self, init: T, apply: Apply, suffix: &str)
     where
-        Apply: Fn(DelimIter<'_>, &mut BufWriter<File>, &mut T) + Send + Sync,
+        Apply: Fn(DelimIter<'_>, &mut Vec<u8>, &mut T) -> bool + Send + Sync,
         T: Clone + Send + Sync,
     {
+        self.assert_whole_files();
         self.paths.par_iter().for_each(|path| {
             let file = File::open(path).unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
             let reader = BufReader::with_capacity(BUFSIZE, file);
-            let mut fname = path.file_name().expect("file name").to_owned();
-            fname.push(&suffix);
-            let new_path = path.with_file_name(fname);
-            let file = File::create(&new_path).expect("write file");
-            let mut writer = BufWriter::with_capacity(BUFSIZE, file);
+            let mut writer = sink_writer(path, suffix);
 
             let mut agg = init.clone();
+            let mut out = Vec::new();
             for line in reader.split(b'\n') {
                 let line = line.expect("line read");
-                apply(DelimIter::new(&line, self.delimiter), &mut writer, &mut agg);
+                out.clear();
+                if apply(DelimIter::new(&line, self.delimiter), &mut out, &mut agg) {
+                    writer.write_all(&out).expect("for each sink write");
+                }
             }
             writer.flush().expect("for each sink flush");
         })
     }
+
+    /// Like [`Scanner::for_each_sink`], but lines where `apply` fails are
+    /// dropped from the output and passed, along with the error, to
+    /// `on_error`.
+    pub fn for_each_sink_with_errors<Apply, OnError, T, E>(
+        &self,
+        init: T,
+        apply: Apply,
+        on_error: OnError,
+        suffix: &str,
+    ) where
+        Apply: Fn(DelimIter<'_>, &mut Vec<u8>, &mut T) -> Result<(), E> + Send + Sync,
+        OnError: Fn(&[u8], E) + Send + Sync,
+        T: Clone + Send + Sync,
+    {
+        self.assert_whole_files();
+        self.paths.par_iter().for_each(|path| {
+            let file = File::open(path).unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+            let reader = BufReader::with_capacity(BUFSIZE, file);
+            let mut writer = sink_writer(path, suffix);
+
+            let mut agg = init.clone();
+            let mut out = Vec::new();
+            for line in reader.split(b'\n') {
+                let line = line.expect("line read");
+                out.clear();
+                match apply(DelimIter::new(&line, self.delimiter), &mut out, &mut agg) {
+                    Ok(()) => writer.write_all(&out).expect("for each sink write"),
+                    Err(e) => on_error(&line, e),
+                }
+            }
+            writer.flush().expect("for each sink flush");
+        })
+    }
+
+    /// Like [`Scanner::for_each_sink`], but also parallel within each file,
+    /// which is split into one byte range per thread, snapped forward to line
+    /// boundaries. The output is the same as the sequential version's, except
+    /// that each range gets its own clone of `init` to aggregate into.
+    ///
+    /// Each range writes to a temporary `<output>.part<i>` file alongside the
+    /// output, and these are stitched together in order at the end.
+    pub fn for_each_sink_parallel_lines<Apply, T>(&self, init: T, apply: Apply, suffix: &str)
+    where
+        Apply: Fn(DelimIter<'_>, &mut Vec<u8>, &mut T) -> bool + Send + Sync,
+        T: Clone + Send + Sync,
+    {
+        self.assert_whole_files();
+        let nchunks = rayon::current_num_threads();
+        self.paths.par_iter().for_each(|path| {
+            let mut fname = path.file_name().expect("file name").to_owned();
+            fname.push(suffix);
+            let new_path = path.with_file_name(fname);
+            let bounds = line_aligned_bounds(path, nchunks);
+            let part_path = |i: usize| {
+                let mut fname = new_path.file_name().unwrap().to_owned();
+                fname.push(format!(".part{}", i));
+                new_path.with_file_name(fname)
+            };
+
+            bounds.par_windows(2).enumerate().for_each(|(i, range)| {
+                let mut file =
+                    File::open(path).unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+                file.seek(SeekFrom::Start(range[0])).expect("seek chunk");
+                let reader = BufReader::with_capacity(BUFSIZE, file.take(range[1] - range[0]));
+                let file = File::create(part_path(i)).expect("write file");
+                let mut writer = BufWriter::with_capacity(BUFSIZE, file);
+
+                let mut agg = init.clone();
+                let mut out = Vec::new();
+                for line in reader.split(b'\n') {
+                    let line = line.expect("line read");
+                    out.clear();
+                    if apply(DelimIter::new(&line, self.delimiter), &mut out, &mut agg) {
+                        writer.write_all(&out).expect("for each sink write");
+                    }
+                }
+                writer.flush().expect("for each sink flush");
+            });
+
+            let file = File::create(&new_path).expect("write file");
+            let mut writer = BufWriter::with_capacity(BUFSIZE, file);
+            for i in 0..bounds.len() - 1 {
+                let mut part = File::open(part_path(i)).expect("read part");
+                io::copy(&mut part, &mut writer).expect("copy part");
+                std::fs::remove_file(part_path(i)).expect("remove part");
+            }
+            writer.flush().expect("for each sink flush");
+        })
+    }
+}
+
+/// Creates the output file for `path` in the `for_each_sink` family, named
+/// like `path` with `suffix` appended.
+fn sink_writer(path: &Path, suffix: &str) -> BufWriter<File> {
+    let mut fname = path.file_name().expect("file name").to_owned();
+    fname.push(suffix);
+    let file = File::create(path.with_file_name(fname)).expect("write file");
+    BufWriter::with_capacity(BUFSIZE, file)
+}
+
+/// Returns `nchunks + 1` byte offsets splitting the file at `path` into
+/// ranges, starting at the file's start and otherwise just past a newline,
+/// some of which may be empty.
+fn line_aligned_bounds(path: &Path, nchunks: usize) -> Vec<u64> {
+    let mut file = File::open(path).unwrap_or_else(|e| panic!("read file: {:?}\n{}", path, e));
+    let len = file.metadata().expect("file metadata").len();
+    let mut bounds = vec![0];
+    let mut buf = vec![0u8; BUFSIZE];
+    for i in 1..nchunks as u64 {
+        let mut pos = (len * i / nchunks as u64).max(*bounds.last().unwrap());
+        file.seek(SeekFrom::Start(pos)).expect("seek chunk");
+        // scan for the end of the line straddling pos, unless pos is already
+        // at a line start
+        let at_line_start = pos == 0 || {
+            let mut prev = [0u8];
+            file.seek(SeekFrom::Start(pos - 1)).expect("seek chunk");
+            file.read_exact(&mut prev).expect("chunk read");
+            prev[0] == b'\n'
+        };
+        while !at_line_start && pos < len {
+            let nread = file.read(&mut buf).expect("chunk read");
+            match memchr::memchr(b'\n', &buf[..nread]) {
+                Some(ix) => {
+                    pos += ix as u64 + 1;
+                    break;
+                }
+                None => pos += nread as u64,
+            }
+        }
+        bounds.push(pos.min(len));
+    }
+    bounds.push(len);
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn line_count_across_files() {
+        let dir = crate::test_dir("line_count_across_files");
+        fs::write(dir.join("a.txt"), "0 1\n1 0\n2\n").unwrap();
+        fs::write(dir.join("b.txt"), "3 4\nno trailing newline").unwrap();
+        fs::write(dir.join("c.txt"), "").unwrap();
+        let paths = ["a.txt", "b.txt", "c.txt"].iter().map(|f| dir.join(f));
+        let scanner = Scanner::new(paths.collect(), b' ');
+        assert_eq!(scanner.line_count(), 5);
+    }
+
+    #[test]
+    fn parse_words() {
+        let mut parsed = Vec::new();
+        DelimIter::new(b"3 14 159 26", b' ').for_each_parsed_u32(|x| parsed.push(x));
+        assert_eq!(parsed, vec![3, 14, 159, 26]);
+        let mut parsed = Vec::new();
+        DelimIter::new(b"", b' ').for_each_parsed_u32(|x| parsed.push(x));
+        assert!(parsed.is_empty());
+        let mut line = DelimIter::new(b"3 14 159 26", b' ');
+        let words = line.collect_smallvec::<4>();
+        assert!(!words.spilled());
+        assert_eq!(words.as_slice(), [&b"3"[..], b"14", b"159", b"26"]);
+    }
+
+    #[test]
+    fn for_each_sink_filters_lines() {
+        let dir = crate::test_dir("for_each_sink_filters_lines");
+        let path = dir.join("lines.txt");
+        let contents: String = (0..10).map(|i| format!("{} {}\n", i, i + 1)).collect();
+        fs::write(&path, contents).unwrap();
+
+        let scanner = Scanner::new(vec![path.clone()], b' ');
+        scanner.for_each_sink(
+            (),
+            |mut line, out, _| {
+                let first: u32 = std::str::from_utf8(line.next().unwrap())
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                // odd lines are written out before being dropped
+                writeln!(out, "{}", first).unwrap();
+                first.is_multiple_of(2)
+            },
+            ".out",
+        );
+        let out = fs::read_to_string(dir.join("lines.txt.out")).unwrap();
+        assert_eq!(out, "0\n2\n4\n6\n8\n");
+    }
+
+    #[test]
+    fn parallel_lines_sink_matches_sequential() {
+        let dir = crate::test_dir("parallel_lines_sink_matches_sequential");
+        let path = dir.join("lines.txt");
+        let contents: String = (0..1000)
+            .map(|i| format!("{} {} {}\n", i, 2 * i, i % 7))
+            .collect();
+        fs::write(&path, contents).unwrap();
+
+        let apply = |line: DelimIter<'_>, out: &mut Vec<u8>, _: &mut ()| {
+            let words: Vec<&[u8]> = line.collect();
+            out.extend_from_slice(words[1]);
+            out.push(b'\n');
+            words[2] != b"0"
+        };
+        let scanner = Scanner::new(vec![path], b' ');
+        scanner.for_each_sink((), apply, ".seq");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        pool.install(|| scanner.for_each_sink_parallel_lines((), apply, ".par"));
+
+        let sequential = fs::read(dir.join("lines.txt.seq")).unwrap();
+        assert_eq!(fs::read(dir.join("lines.txt.par")).unwrap(), sequential);
+        assert_eq!(sequential.iter().filter(|&&b| b == b'\n').count(), 857);
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["lines.txt", "lines.txt.par", "lines.txt.seq"]);
+    }
+
+    #[test]
+    fn from_glob_reads_shards() {
+        let dir = crate::test_dir("from_glob_reads_shards");
+        fs::write(dir.join("graph-0.txt"), "0 1 2\n1 2\n").unwrap();
+        fs::write(dir.join("graph-1.txt"), "2 3\n3\n").unwrap();
+        fs::write(dir.join("other.txt"), "9 10\n").unwrap();
+        fs::create_dir(dir.join("graph-dir.txt")).unwrap();
+
+        let pattern = dir.join("graph-*.txt");
+        let scanner = Scanner::from_glob(pattern.to_str().unwrap(), b' ').unwrap();
+        assert_eq!(
+            scanner.paths,
+            vec![dir.join("graph-0.txt"), dir.join("graph-1.txt")]
+        );
+        let graph = crate::graphio::read(&scanner);
+        assert_eq!(graph.nvertices(), 4);
+        assert_eq!(
+            graph.edges().collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 2), (2, 3)]
+        );
+
+        let missing = dir.join("nothing-*.txt");
+        assert!(matches!(
+            Scanner::from_glob(missing.to_str().unwrap(), b' '),
+            Err(GlobError::NoMatches(_))
+        ));
+        assert!(matches!(
+            Scanner::from_glob("[", b' '),
+            Err(GlobError::Pattern(_))
+        ));
+    }
+
+    #[test]
+    fn single_file_chunks_read_the_same_graph() {
+        let dir = crate::test_dir("single_file_chunks_read_the_same_graph");
+        let graph = crate::generators::watts_strogatz(500, 6, 0.3, 1).unwrap();
+        let prefix = dir.join("graph");
+        crate::graphio::write(&graph, &prefix, graph.nvertices()).unwrap();
+        let path = dir.join("graph.0");
+
+        let whole = Scanner::from_single_file_parallel(path.clone(), 1, b' ');
+        let chunked = Scanner::from_single_file_parallel(path, 8, b' ');
+        assert_eq!(whole.line_count(), graph.nvertices());
+        assert_eq!(chunked.line_count(), graph.nvertices());
+        let read = crate::graphio::read(&whole);
+        assert_eq!(read, graph);
+        assert_eq!(crate::graphio::read(&chunked), read);
+
+        // more chunks than lines leaves some empty
+        let tiny = dir.join("tiny.txt");
+        fs::write(&tiny, "0 1\n1 2\n").unwrap();
+        let chunked = Scanner::from_single_file_parallel(tiny, 8, b' ');
+        assert_eq!(chunked.line_count(), 2);
+        assert_eq!(crate::graphio::read(&chunked).nedges(), 2);
+    }
 }
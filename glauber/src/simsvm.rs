@@ -3,7 +3,9 @@
 //! <target> <feature> <feature>...
 //! where target and features should be contiguous non-negative integers.
 
-use crate::scanner::DelimIter;
+use rayon::iter::ParallelIterator;
+
+use crate::scanner::{DelimIter, Scanner};
 
 /// Given a [`DelimIter`] pointing to the front of a line in a
 /// simsvm file, this wrapper is a convenient iterator over
@@ -19,6 +21,41 @@ pub fn parse(mut iter: DelimIter<'_>) -> SimSvmLineIter<'_> {
     SimSvmLineIter { target, iter }
 }
 
+/// Returns the largest feature across every line of the scanned files, or zero
+/// if there are no features.
+pub fn max_feature(scanner: &Scanner) -> u32 {
+    scanner
+        .fold(|_| 0, |m, line| parse(line).max().unwrap_or(0).max(m))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the number of occurrences of each feature across every line of the
+/// scanned files, indexed by feature, up to the largest feature present.
+pub fn feature_count_histogram(scanner: &Scanner) -> Vec<u32> {
+    scanner
+        .fold(
+            |_| Vec::new(),
+            |mut counts: Vec<u32>, line| {
+                for feature in parse(line) {
+                    let feature = feature as usize;
+                    if feature >= counts.len() {
+                        counts.resize(feature + 1, 0);
+                    }
+                    counts[feature] += 1;
+                }
+                counts
+            },
+        )
+        .reduce(Vec::new, |mut a, mut b| {
+            if a.len() < b.len() {
+                std::mem::swap(&mut a, &mut b);
+            }
+            a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+            a
+        })
+}
+
 impl<'a> Iterator for SimSvmLineIter<'a> {
     type Item = u32;
 
@@ -31,6 +68,12 @@ impl<'a> Iterator for SimSvmLineIter<'a> {
 }
 
 impl<'a> SimSvmLineIter<'a> {
+    /// Calls `f` on each remaining feature, like iterating, but parsing with
+    /// [`DelimIter::for_each_parsed_u32`].
+    pub fn for_each_feature<F: FnMut(u32)>(mut self, f: F) {
+        self.iter.for_each_parsed_u32(f)
+    }
+
     pub fn target(&self) -> u32 {
         std::str::from_utf8(self.target)
             .expect("utf-8")
@@ -38,3 +81,36 @@ impl<'a> SimSvmLineIter<'a> {
             .expect("parse u32 target")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn feature_statistics() {
+        let dir = crate::test_dir("simsvm_feature_statistics");
+        let paths = vec![dir.join("a.svm"), dir.join("b.svm")];
+        fs::write(&paths[0], "1 3 5 7\n0 2\n").unwrap();
+        fs::write(&paths[1], "1 5 12\n0\n").unwrap();
+        let scanner = Scanner::new(paths, b' ');
+        assert_eq!(max_feature(&scanner), 12);
+        let mut expected = vec![0; 13];
+        for f in [3, 5, 7, 2, 5, 12] {
+            expected[f] += 1;
+        }
+        assert_eq!(feature_count_histogram(&scanner), expected);
+    }
+
+    #[test]
+    fn parse_line() {
+        let line = parse(DelimIter::new(b"4 10 20 30", b' '));
+        assert_eq!(line.target(), 4);
+        assert_eq!(line.clone().collect::<Vec<_>>(), vec![10, 20, 30]);
+        let mut features = Vec::new();
+        line.for_each_feature(|f| features.push(f));
+        assert_eq!(features, vec![10, 20, 30]);
+        assert_eq!(parse(DelimIter::new(b"4", b' ')).count(), 0);
+    }
+}